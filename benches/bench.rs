@@ -1,37 +1,43 @@
 use aoc2025 as aoc_lib;
+use aoc_lib::utils::Part;
 use criterion::{criterion_group, criterion_main, Criterion};
 
-macro_rules! benchmarks_internal {
-  ( $($day:ident),* ) => {
-    paste::paste!{
-      $(fn [<$day _benchmark>](c: &mut Criterion) {
-          use aoc_lib::$day;
-          let input_data = aoc_lib::utils::read_inputs("input", &vec![stringify!($day)])
-            .expect("can't read input");
-          let input = $day::generator(&input_data[0]);
-          c.bench_function(concat!(stringify!($day), " gen"), |b| {
-            b.iter(|| $day::generator(&input_data[0]))
-          });
-          c.bench_function(concat!(stringify!($day), " part 1"), |b| {
-            b.iter(|| $day::part1(&input))
-          });
-          c.bench_function(concat!(stringify!($day), " part 2"), |b| {
-            b.iter(|| $day::part2(&input))
-          });
-        }
-        criterion_group!($day, [<$day _benchmark>]);
-      )*
-
-      criterion_main!($($day),*);
+/// Benchmark the generator and both parts of every registered day. Each day is
+/// its own group, so a single day can be run with `cargo bench -- day3/`.
+/// Days without an input file, or whose input doesn't solve, are skipped.
+fn benchmarks(c: &mut Criterion) {
+  for day in aoc_lib::DAYS.iter() {
+    let name = day.name();
+    let input = match aoc_lib::utils::read_inputs("input", &[&name]).remove(0) {
+      Ok(input) => input,
+      Err(e) => {
+        eprintln!("Skipping {name}: can't read input ({e})");
+        continue;
+      }
+    };
+    let parsed = match day.generate(&input) {
+      Ok(parsed) => parsed,
+      Err(e) => {
+        eprintln!("Skipping {name}: {e}");
+        continue;
+      }
+    };
+    let mut group = c.benchmark_group(&name);
+    group.bench_function("gen", |b| {
+      b.iter(|| day.generate(&input))
+    });
+    for part in Part::ALL {
+      if let Err(e) = day.solve(parsed.as_ref(), part) {
+        eprintln!("Skipping {name} part {part}: {e}");
+        continue;
+      }
+      group.bench_function(format!("part {part}"), |b| {
+        b.iter(|| day.solve(parsed.as_ref(), part))
+      });
     }
-  };
-}
-
-#[macro_export]
-macro_rules! benchmarks {
-  ( $($day:literal),* ) => {
-    paste::paste!{ benchmarks_internal!{$( [<day $day>] ),*} }
+    group.finish();
   }
 }
 
-benchmarks!(1);
\ No newline at end of file
+criterion_group!(benches, benchmarks);
+criterion_main!(benches);
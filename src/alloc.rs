@@ -0,0 +1,164 @@
+use crate::utils::Part;
+use serde::Serialize;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt;
+
+/// A global allocator that counts the bytes allocated by each thread. It is
+/// installed by the alloc-stats feature, since the counting slows down every
+/// allocation.
+pub struct Counting;
+
+thread_local! {
+  // the bytes that are live relative to the start of tracking, which can go
+  // negative when memory from before then is freed
+  static LIVE: Cell<i64> = const { Cell::new(0) };
+  static PEAK: Cell<i64> = const { Cell::new(0) };
+  static TOTAL: Cell<u64> = const { Cell::new(0) };
+}
+
+fn record_alloc(size: usize) {
+  // Allocations while the thread is being torn down aren't counted
+  let _ = LIVE.try_with(|live| {
+    live.set(live.get() + size as i64);
+    let _ = PEAK.try_with(|peak| peak.set(peak.get().max(live.get())));
+    let _ = TOTAL.try_with(|total| total.set(total.get() + size as u64));
+  });
+}
+
+fn record_dealloc(size: usize) {
+  let _ = LIVE.try_with(|live| live.set(live.get() - size as i64));
+}
+
+unsafe impl GlobalAlloc for Counting {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    record_alloc(layout.size());
+    System.alloc(layout)
+  }
+
+  unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+    record_alloc(layout.size());
+    System.alloc_zeroed(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    record_dealloc(layout.size());
+    System.dealloc(ptr, layout)
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    record_dealloc(layout.size());
+    record_alloc(new_size);
+    System.realloc(ptr, layout, new_size)
+  }
+}
+
+/// The memory used while running a phase.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct AllocStats {
+  /// the most bytes that were live at once beyond those from before the phase
+  pub peak: u64,
+  /// the bytes allocated, including those that were later freed
+  pub total: u64,
+}
+
+/// Format a number of bytes with a binary unit.
+fn format_bytes(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+  let mut value = bytes as f64;
+  let mut unit = 0;
+  while value >= 1024.0 && unit < UNITS.len() - 1 {
+    value /= 1024.0;
+    unit += 1;
+  }
+  match unit {
+    0 => format!("{bytes} B"),
+    _ => format!("{value:.2} {}", UNITS[unit]),
+  }
+}
+
+impl fmt::Display for AllocStats {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "peak {} total {}", format_bytes(self.peak), format_bytes(self.total))
+  }
+}
+
+/// The memory used by each phase of a day.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct DayAllocs {
+  pub generate: AllocStats,
+  pub part1: AllocStats,
+  pub part2: AllocStats,
+}
+
+impl DayAllocs {
+  pub fn part(&self, part: Part) -> AllocStats {
+    match part {
+      Part::One => self.part1,
+      Part::Two => self.part2,
+    }
+  }
+
+  fn part_mut(&mut self, part: Part) -> &mut AllocStats {
+    match part {
+      Part::One => &mut self.part1,
+      Part::Two => &mut self.part2,
+    }
+  }
+
+  /// Record the stats for the generator, or for a part if one is given.
+  pub fn record(&mut self, part: Option<Part>, stats: AllocStats) {
+    match part {
+      Some(part) => *self.part_mut(part) = stats,
+      None => self.generate = stats,
+    }
+  }
+}
+
+/// Is the counting allocator installed?
+pub fn enabled() -> bool {
+  cfg!(feature = "alloc-stats")
+}
+
+/// Run the function, counting what it allocates on the current thread. The
+/// stats are None when the counting allocator isn't installed.
+pub fn track<T>(func: &dyn Fn() -> T) -> (Option<AllocStats>, T) {
+  LIVE.with(|live| live.set(0));
+  PEAK.with(|peak| peak.set(0));
+  TOTAL.with(|total| total.set(0));
+  let result = func();
+  let stats = AllocStats {
+    peak: PEAK.with(|peak| peak.get()).max(0) as u64,
+    total: TOTAL.with(|total| total.get()),
+  };
+  (enabled().then_some(stats), result)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{enabled, format_bytes, track};
+
+  #[test]
+  fn test_format_bytes() {
+    assert_eq!(format_bytes(10), "10 B");
+    assert_eq!(format_bytes(1536), "1.50 KiB");
+    assert_eq!(format_bytes(3 << 20), "3.00 MiB");
+  }
+
+  #[test]
+  fn test_track() {
+    let (stats, len) = track(&|| {
+      let big = vec![0u8; 4096];
+      drop(big);
+      vec![1u64; 100].len()
+    });
+    assert_eq!(len, 100);
+    match stats {
+      Some(stats) => {
+        assert_eq!(stats.peak, 4096);
+        assert_eq!(stats.total, 4096 + 800);
+      }
+      None => assert!(!enabled()),
+    }
+  }
+}
@@ -1,48 +1,61 @@
+use crate::utils::{parse_token, ParseError, Solution, SolveError};
+
 const START: i32 = 50;
 const DIAL_SIZE: i32 = 100;
 
-pub fn part1(rotations: &[i32]) -> u32 {
-    let mut pos = START;
-    let mut count = 0;
-    for r in rotations.iter() {
-        pos = (pos + r).rem_euclid(DIAL_SIZE);
-        if pos == 0 {
-            count += 1;
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: usize = 1;
+    const TITLE: &'static str = "Secret Entrance";
+    type Input = Vec<i32>;
+    type Output1 = u32;
+    type Output2 = u32;
+
+    fn part1(rotations: &Vec<i32>) -> Result<u32, SolveError> {
+        let mut pos = START;
+        let mut count = 0;
+        for r in rotations.iter() {
+            pos = (pos + r).rem_euclid(DIAL_SIZE);
+            if pos == 0 {
+                count += 1;
+            }
         }
+        Ok(count)
     }
-    count
-}
 
-pub fn part2(rotations: &[i32]) -> u32 {
-    let mut pos = START;
-    let mut count = 0u32;
-    for r in rotations.iter() {
-        if pos > 0 && pos + r <= 0 {
-            count += 1;
+    fn part2(rotations: &Vec<i32>) -> Result<u32, SolveError> {
+        let mut pos = START;
+        let mut count = 0u32;
+        for r in rotations.iter() {
+            if pos > 0 && pos + r <= 0 {
+                count += 1;
+            }
+            count += ((pos + r) / DIAL_SIZE).unsigned_abs();
+            pos = (pos + r).rem_euclid(DIAL_SIZE);
         }
-        count += ((pos + r) / DIAL_SIZE).unsigned_abs();
-        pos = (pos + r).rem_euclid(DIAL_SIZE);
+        Ok(count)
     }
-    count
-}
 
-pub fn generator(input: &str) -> Vec<i32> {
-    input.lines()
-        .map(|x| {
-            if let Some(stripped) = x.strip_prefix('L') {
-                -stripped.parse::<i32>().unwrap()
-            } else if let Some(stripped) = x.strip_prefix('R') {
-                stripped.parse::<i32>().unwrap()
-            } else {
-                panic!("Invalid input: {}", x);
-            }
-        })
-        .collect()
+    fn generator(input: &str) -> Result<Vec<i32>, ParseError> {
+        input.lines()
+            .map(|x| {
+                if let Some(stripped) = x.strip_prefix('L') {
+                    Ok(-parse_token::<i32>(Self::DAY, input, stripped)?)
+                } else if let Some(stripped) = x.strip_prefix('R') {
+                    parse_token(Self::DAY, input, stripped)
+                } else {
+                    Err(ParseError::new(Self::DAY, input, x, "Invalid rotation"))
+                }
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{generator, part1, part2};
+    use super::Day1;
+    use crate::utils::Solution;
 
     const INPUT: &str = "L68
 L30
@@ -57,35 +70,40 @@ L82";
 
     #[test]
     fn test_generator() {
-        let rotations = generator(INPUT);
+        let rotations = Day1::generator(INPUT).unwrap();
         assert_eq!(10, rotations.len());
         assert_eq!(vec![-68, -30, 48, -5, 60, -55, -1, -99, 14, -82], rotations);
+
+        let e = Day1::generator("L68\nX30").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 1, "X30"));
+        let e = Day1::generator("L68\nR3x").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 2, "3x"));
     }
 
     #[test]
     fn test_part_1() {
-        let rotations = generator(INPUT);
-        assert_eq!(3, part1(&rotations));
+        let rotations = Day1::generator(INPUT).unwrap();
+        assert_eq!(3, Day1::part1(&rotations).unwrap());
     }
 
     #[test]
     fn test_part_2() {
-        let rotations = generator(INPUT);
-        assert_eq!(6, part2(&rotations));
+        let rotations = Day1::generator(INPUT).unwrap();
+        assert_eq!(6, Day1::part2(&rotations).unwrap());
 
-        let rotations = generator("R1000");
-        assert_eq!(10, part2(&rotations));
+        let rotations = Day1::generator("R1000").unwrap();
+        assert_eq!(10, Day1::part2(&rotations).unwrap());
 
-        let rotations = generator("R1000\nR1000");
-        assert_eq!(20, part2(&rotations));
+        let rotations = Day1::generator("R1000\nR1000").unwrap();
+        assert_eq!(20, Day1::part2(&rotations).unwrap());
 
-        let rotations = generator("R1000\nR1000\nL1000");
-        assert_eq!(30, part2(&rotations));
+        let rotations = Day1::generator("R1000\nR1000\nL1000").unwrap();
+        assert_eq!(30, Day1::part2(&rotations).unwrap());
 
-        let rotations = generator("L48\nL3\nR5\nL106");
-        assert_eq!(4, part2(&rotations));
+        let rotations = Day1::generator("L48\nL3\nR5\nL106").unwrap();
+        assert_eq!(4, Day1::part2(&rotations).unwrap());
 
-        let rotations = generator("L48\nL102");
-        assert_eq!(2, part2(&rotations));
+        let rotations = Day1::generator("L48\nL102").unwrap();
+        assert_eq!(2, Day1::part2(&rotations).unwrap());
     }
 }
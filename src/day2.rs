@@ -1,11 +1,41 @@
 use itertools::Itertools;
+use crate::utils::{parse_token, ParseError, Solution, SolveError};
 
-pub fn part1(ranges: &[(u64, u64)]) -> u64 {
-    find_invalid_ids(ranges, true).iter().sum()
-}
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: usize = 2;
+    const TITLE: &'static str = "Gift Shop";
+    type Input = Vec<(u64, u64)>;
+    type Output1 = u64;
+    type Output2 = u64;
+
+    fn part1(ranges: &Vec<(u64, u64)>) -> Result<u64, SolveError> {
+        Ok(find_invalid_ids(ranges, true).iter().sum())
+    }
+
+    fn part2(ranges: &Vec<(u64, u64)>) -> Result<u64, SolveError> {
+        Ok(find_invalid_ids(ranges, false).iter().sum())
+    }
 
-pub fn part2(ranges: &[(u64, u64)]) -> u64 {
-    find_invalid_ids(ranges, false).iter().sum()
+    fn generator(input: &str) -> Result<Vec<(u64, u64)>, ParseError> {
+        // Keep the text of each range to report overlaps
+        let mut ranges: Vec<(u64, u64, &str)> = Vec::new();
+        for i in input.split(',') {
+            let range = i.split_once('-')
+                .ok_or_else(|| ParseError::new(Self::DAY, input, i, "Expected a range"))?;
+            let l = parse_token(Self::DAY, input, range.0)?;
+            let h = parse_token(Self::DAY, input, range.1)?;
+            ranges.extend(find_ranges(&l, &h).into_iter().map(|(l, h)| (l, h, i)));
+        }
+        // We should not have any overlapping ranges
+        ranges.sort_unstable_by_key(|r| r.0);
+        if let Some(w) = ranges.windows(2).find(|w| w[0].1 >= w[1].0) {
+            return Err(ParseError::new(Self::DAY, input, w[1].2,
+                                       "Overlapping ranges are not supported"));
+        }
+        Ok(ranges.into_iter().map(|(l, h, _)| (l, h)).collect())
+    }
 }
 
 fn find_invalid_ids(ranges: &[(u64, u64)], single_split: bool) -> Vec<u64> {
@@ -49,41 +79,28 @@ fn find_ranges(l: &u64, h: &u64) -> Vec<(u64, u64)> {
     output
 }
 
-pub fn generator(input: &str) -> Vec<(u64, u64)> {
-    let mut ranges: Vec<(u64, u64)> = input.trim().split(',')
-        .flat_map(|i| {
-            let range = i.split_once('-').unwrap();
-            find_ranges(&range.0.parse().unwrap(), &range.1.parse().unwrap())
-        }).collect();
-    // We should not have any overlapping ranges
-    ranges.sort_unstable_by_key(|r| r.0);
-    if ranges.windows(2).all(|w| w[0].1 < w[1].0) == false {
-        panic!("Overlapping ranges are not supported!!!")
-    }
-    ranges
-}
-
 #[cfg(test)]
 mod tests {
-    use super::{generator, part1, part2};
+    use super::Day2;
+    use crate::utils::Solution;
 
     const INPUT: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
 
     #[test]
     fn test_generator() {
-        let ranges = generator(INPUT);
+        let ranges = Day2::generator(INPUT).unwrap();
         assert_eq!(13, ranges.len());
     }
 
     #[test]
     fn test_part_1() {
-        let ranges = generator(INPUT);
-        assert_eq!(1227775554, part1(&ranges));
+        let ranges = Day2::generator(INPUT).unwrap();
+        assert_eq!(1227775554, Day2::part1(&ranges).unwrap());
     }
 
     #[test]
     fn test_part_2() {
-        let ranges = generator(INPUT);
-        assert_eq!(4174379265, part2(&ranges));
+        let ranges = Day2::generator(INPUT).unwrap();
+        assert_eq!(4174379265, Day2::part2(&ranges).unwrap());
     }
 }
@@ -1,16 +1,42 @@
-pub fn part1(battery_banks: &[Vec<u8>]) -> u64 {
-    compute_total_joltage(battery_banks, 2)
-}
+use crate::utils::{ParseError, Solution, SolveError};
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: usize = 3;
+    const TITLE: &'static str = "Lobby";
+    type Input = Vec<Vec<u8>>;
+    type Output1 = u64;
+    type Output2 = u64;
 
-pub fn part2(battery_banks: &[Vec<u8>]) -> u64 {
-    compute_total_joltage(battery_banks, 12)
+    fn part1(battery_banks: &Vec<Vec<u8>>) -> Result<u64, SolveError> {
+        compute_total_joltage(battery_banks, 2)
+    }
+
+    fn part2(battery_banks: &Vec<Vec<u8>>) -> Result<u64, SolveError> {
+        compute_total_joltage(battery_banks, 12)
+    }
+
+    fn generator(input: &str) -> Result<Vec<Vec<u8>>, ParseError> {
+        input.lines()
+            .map(|l| l.char_indices()
+                .map(|(i, c)| c.to_digit(10).map(|d| d as u8).ok_or_else(||
+                    ParseError::new(Self::DAY, input, &l[i..i + c.len_utf8()],
+                                    "Expected a digit")))
+                .collect())
+            .collect()
+    }
 }
 
-fn compute_total_joltage(battery_banks: &[Vec<u8>], select: usize) -> u64 {
+fn compute_total_joltage(battery_banks: &[Vec<u8>], select: usize) -> Result<u64, SolveError> {
     let mut lefts: Vec<Vec<usize>> = vec![Vec::new(); 10];
     battery_banks
         .iter()
         .map(|b| {
+            if b.len() < select {
+                return Err(SolveError::new(
+                    format!("A bank of {} batteries can't turn on {select}", b.len())));
+            }
             let mut joltage = 0u64;
             let mut start_pos = 0;
             lefts.iter_mut().for_each(|l| l.clear());
@@ -24,23 +50,18 @@ fn compute_total_joltage(battery_banks: &[Vec<u8>], select: usize) -> u64 {
                         i.iter().find(|idx| idx >= &&start_pos && idx < &&(b.len() - r))
                             .map(|idx| (j, idx))
                     })
-                    .next_back().unwrap();
+                    .next_back().expect("There is always a battery left");
                 joltage = joltage * 10 + first.0 as u64;
                 start_pos = first.1 + 1;
             }
-            joltage
+            Ok(joltage)
         }).sum()
 }
 
-pub fn generator(input: &str) -> Vec<Vec<u8>> {
-    input.lines()
-        .map(|l| l.chars().map(|c| c.to_digit(10).unwrap() as u8).collect())
-        .collect()
-}
-
 #[cfg(test)]
 mod tests {
-    use super::{generator, part1, part2};
+    use super::Day3;
+    use crate::utils::Solution;
 
     const INPUT: &str = "987654321111111
 811111111111119
@@ -51,22 +72,22 @@ mod tests {
 
     #[test]
     fn test_generator() {
-        let battery_banks = generator(INPUT);
+        let battery_banks = Day3::generator(INPUT).unwrap();
         assert_eq!(battery_banks.len(), 4);
     }
 
     #[test]
     fn test_part_1() {
-        let battery_banks = generator(INPUT);
-        assert_eq!(part1(&battery_banks), 357);
+        let battery_banks = Day3::generator(INPUT).unwrap();
+        assert_eq!(Day3::part1(&battery_banks).unwrap(), 357);
 
-        let battery_banks = generator(INPUT_2);
-        assert_eq!(part1(&battery_banks), 99);
+        let battery_banks = Day3::generator(INPUT_2).unwrap();
+        assert_eq!(Day3::part1(&battery_banks).unwrap(), 99);
     }
 
     #[test]
     fn test_part_2() {
-        let battery_banks = generator(INPUT);
-        assert_eq!(part2(&battery_banks), 3121910778619);
+        let battery_banks = Day3::generator(INPUT).unwrap();
+        assert_eq!(Day3::part2(&battery_banks).unwrap(), 3121910778619);
     }
 }
@@ -1,17 +1,28 @@
 use crate::day4::Space::Paper;
 use crate::day4::Space::Empty;
+use crate::utils::{Grid, ParseError, Solution, SolveError};
 
-pub fn part1(diagram: &Diagram) -> u32 {
-    diagram.accessible_rolls.len() as u32
-}
+pub struct Day4;
 
-pub fn part2(diagram: &Diagram) -> u32 {
-    let mut diagram = diagram.clone();
-    diagram.remove_rolls(true)
-}
+impl Solution for Day4 {
+    const DAY: usize = 4;
+    const TITLE: &'static str = "Printing Department";
+    type Input = Diagram;
+    type Output1 = u32;
+    type Output2 = u32;
+
+    fn part1(diagram: &Diagram) -> Result<u32, SolveError> {
+        Ok(diagram.accessible_rolls.len() as u32)
+    }
 
-pub fn generator(input: &str) -> Diagram {
-    Diagram::new(input)
+    fn part2(diagram: &Diagram) -> Result<u32, SolveError> {
+        let mut diagram = diagram.clone();
+        Ok(diagram.remove_rolls(true))
+    }
+
+    fn generator(input: &str) -> Result<Diagram, ParseError> {
+        Diagram::new(input)
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -44,19 +55,17 @@ impl Space {
 
 #[derive(Clone)]
 pub struct Diagram {
-    layout: Vec<Vec<Space>>,
+    layout: Grid<Space>,
     accessible_rolls: Vec<(usize, usize)>,
 }
 
 impl Diagram {
-    fn new(input: &str) -> Self {
-        let layout = input.lines()
-            .map(|line| line.chars().map(|c| match c {
-                '@' => Paper(0),
-                '.' => Empty,
-                _ => panic!("Invalid character {c} in input"),
-            }).collect())
-            .collect();
+    fn new(input: &str) -> Result<Self, ParseError> {
+        let layout = Grid::parse(Day4::DAY, input, |_, c| match c {
+            '@' => Some(Paper(0)),
+            '.' => Some(Empty),
+            _ => None,
+        })?;
 
         let mut d = Diagram {
             layout,
@@ -64,77 +73,39 @@ impl Diagram {
         };
         d.compute_adjacent_rolls();
         d.compute_accessible_rolls();
-        d
-    }
-
-    fn width(&self) -> usize {
-        self.layout.first().unwrap().len()
-    }
-
-    fn height(&self) -> usize {
-        self.layout.len()
+        Ok(d)
     }
 
     fn compute_adjacent_rolls(&mut self) {
-        let width = self.width();
-        let height = self.height();
-        for y in 0..height {
-            for x in 0..width {
-                if self.layout[y][x] == Empty { continue; }
-                for nx in [-1i32, 0, 1] {
-                    for ny in [-1i32, 0, 1] {
-                        if nx == 0 && ny == 0 {
-                            continue;
-                        }
-                        match (x as i32 + nx, y as i32 + ny) {
-                            (x, y) if x < 0 || x >= width as i32 || y < 0 || y >= height as i32 => {}
-                            (x, y) => {
-                                self.layout[y as usize][x as usize].increase_adjacent_rolls();
-                            }
-                        }
-                    }
-                }
+        for pos in self.layout.positions() {
+            if self.layout[pos] == Empty { continue; }
+            for neighbor in self.layout.neighbors8(pos) {
+                self.layout[neighbor].increase_adjacent_rolls();
             }
         }
     }
 
     fn compute_accessible_rolls(&mut self) {
-        self.layout.iter_mut().enumerate().for_each(|(y, row)| {
-            row.iter_mut().enumerate().for_each(|(x, s)| {
-                match s {
-                    Paper(n) if n < &mut 4 => {
-                        *s = Empty;
-                        self.accessible_rolls.push((x, y));
-                    }
-                    _ => {}
+        self.layout.iter_mut().for_each(|(pos, s)| {
+            match s {
+                Paper(n) if n < &mut 4 => {
+                    *s = Empty;
+                    self.accessible_rolls.push(pos);
                 }
-            })
+                _ => {}
+            }
         });
     }
 
     fn remove_rolls(&mut self, with_update: bool) -> u32 {
         let mut removed = 0;
-        let width = self.width();
-        let height = self.height();
-        while !self.accessible_rolls.is_empty() {
+        while let Some(pos) = self.accessible_rolls.pop() {
             removed += 1;
-            let (x, y) = self.accessible_rolls.pop().unwrap();
-            for nx in [-1i32, 0, 1] {
-                for ny in [-1i32, 0, 1] {
-                    if nx == 0 && ny == 0 {
-                        continue;
-                    }
-                    match (x as i32 + nx, y as i32 + ny) {
-                        (x, y) if x < 0 || x >= width as i32 || y < 0 || y >= height as i32 => {}
-                        (x, y) => {
-                            if with_update
-                                && self.layout[y as usize][x as usize].decrease_adjacent_rolls()
-                                .is_some_and(|v| v < 4) {
-                                self.layout[y as usize][x as usize] = Empty;
-                                self.accessible_rolls.push((x as usize, y as usize));
-                            }
-                        }
-                    }
+            for neighbor in self.layout.neighbors8(pos) {
+                if with_update
+                    && self.layout[neighbor].decrease_adjacent_rolls().is_some_and(|v| v < 4) {
+                    self.layout[neighbor] = Empty;
+                    self.accessible_rolls.push(neighbor);
                 }
             }
         }
@@ -144,7 +115,8 @@ impl Diagram {
 
 #[cfg(test)]
 mod tests {
-    use super::{generator, part1, part2};
+    use super::Day4;
+    use crate::utils::Solution;
 
     const INPUT: &str = "..@@.@@@@.
 @@@.@.@.@@
@@ -159,20 +131,23 @@ mod tests {
 
     #[test]
     fn test_generator() {
-        let diagram = generator(INPUT);
-        assert_eq!(diagram.width(), 10);
-        assert_eq!(diagram.height(), 10);
+        let diagram = Day4::generator(INPUT).unwrap();
+        assert_eq!(diagram.layout.width(), 10);
+        assert_eq!(diagram.layout.height(), 10);
+
+        let e = Day4::generator("..@\n.#@").err().unwrap();
+        assert_eq!((e.day, e.line, e.column, e.text.as_str()), (4, 2, 2, "#"));
     }
 
     #[test]
     fn test_part_1() {
-        let diagram = generator(INPUT);
-        assert_eq!(part1(&diagram), 13);
+        let diagram = Day4::generator(INPUT).unwrap();
+        assert_eq!(Day4::part1(&diagram).unwrap(), 13);
     }
 
     #[test]
     fn test_part_2() {
-        let diagram = generator(INPUT);
-        assert_eq!(part2(&diagram), 43);
+        let diagram = Day4::generator(INPUT).unwrap();
+        assert_eq!(Day4::part2(&diagram).unwrap(), 43);
     }
 }
@@ -1,15 +1,26 @@
 use std::ops::{RangeInclusive};
+use crate::utils::{parse_token, ParseError, Solution, SolveError};
 
-pub fn part1(inventory: &Inventory) -> u64 {
-    inventory.count_fresh_ingredients()
-}
+pub struct Day5;
 
-pub fn part2(inventory: &Inventory) -> u64 {
-    inventory.fresh.iter().map(|r| r.end() + 1 - r.start()).sum()
-}
+impl Solution for Day5 {
+    const DAY: usize = 5;
+    const TITLE: &'static str = "Cafeteria";
+    type Input = Inventory;
+    type Output1 = u64;
+    type Output2 = u64;
+
+    fn part1(inventory: &Inventory) -> Result<u64, SolveError> {
+        Ok(inventory.count_fresh_ingredients())
+    }
 
-pub fn generator(input: &str) -> Inventory {
-    Inventory::new(input)
+    fn part2(inventory: &Inventory) -> Result<u64, SolveError> {
+        Ok(inventory.fresh.iter().map(|r| r.end() + 1 - r.start()).sum())
+    }
+
+    fn generator(input: &str) -> Result<Inventory, ParseError> {
+        Inventory::new(input)
+    }
 }
 
 pub struct Inventory {
@@ -18,13 +29,16 @@ pub struct Inventory {
 }
 
 impl Inventory {
-    fn new(input: &str) -> Inventory {
-        let (fresh_ranges, ingredients) = input.split_once("\n\n").unwrap();
+    fn new(input: &str) -> Result<Inventory, ParseError> {
+        // The blank line is trimmed with the rest of the input when there are
+        // no ingredients after it
+        let (fresh_ranges, ingredients) = input.split_once("\n\n").unwrap_or((input, ""));
         let mut fresh_ranges: Vec<(u64, u64)> = fresh_ranges.lines()
             .map(|line| {
-                let (low, high) = line.split_once("-").unwrap();
-                (low.parse().unwrap(), high.parse().unwrap())
-            }).collect();
+                let (low, high) = line.split_once("-")
+                    .ok_or_else(|| ParseError::new(Day5::DAY, input, line, "Expected a range"))?;
+                Ok((parse_token(Day5::DAY, input, low)?, parse_token(Day5::DAY, input, high)?))
+            }).collect::<Result<_, _>>()?;
         fresh_ranges.sort();
         let mut fresh: Vec<RangeInclusive<u64>> = Vec::new();
         for (s, e) in fresh_ranges.iter() {
@@ -39,9 +53,10 @@ impl Inventory {
             }
         }
         let ingredients = ingredients.lines()
-            .map(|line| { line.parse().unwrap() }).collect();
+            .map(|line| parse_token(Day5::DAY, input, line))
+            .collect::<Result<_, _>>()?;
 
-        Inventory { fresh, ingredients }
+        Ok(Inventory { fresh, ingredients })
     }
 
     fn is_included(&self, v: &u64) -> bool {
@@ -67,7 +82,9 @@ impl Inventory {
 
 #[cfg(test)]
 mod tests {
-    use super::{generator, part1, part2};
+    use super::Day5;
+    use crate::utils::{Part, Solution};
+    use crate::DAYS;
 
     const INPUT: &str = "3-5
 10-14
@@ -95,23 +112,26 @@ mod tests {
 
     #[test]
     fn test_generator() {
-        let i = generator(INPUT);
+        let i = Day5::generator(INPUT).unwrap();
         assert_eq!(i.ingredients.len(), 6);
         assert_eq!(i.fresh.len(), 4);
+        // The trailing blank line is normalized away
+        assert_eq!(DAYS.solve(5, Part::Two, "1-3\n\n"), Some(Ok("3".to_string())));
+        assert!(Day5::generator("1-3\n5").is_err());
     }
 
     #[test]
     fn test_part_1() {
-        let i = generator(INPUT);
-        assert_eq!(part1(&i), 3);
+        let i = Day5::generator(INPUT).unwrap();
+        assert_eq!(Day5::part1(&i).unwrap(), 3);
     }
 
     #[test]
     fn test_part_2() {
-        let i = generator(INPUT);
-        assert_eq!(part2(&i), 14);
+        let i = Day5::generator(INPUT).unwrap();
+        assert_eq!(Day5::part2(&i).unwrap(), 14);
 
-        let i = generator(INPUT_2);
-        assert_eq!(part2(&i), 13);
+        let i = Day5::generator(INPUT_2).unwrap();
+        assert_eq!(Day5::part2(&i).unwrap(), 13);
     }
 }
@@ -1,52 +1,79 @@
-pub fn part1(homework: &Homework) -> u64 {
-    homework.solve()
-}
+use crate::utils::{parse_token, Normalize, ParseError, Solution, SolveError};
 
-pub fn part2(homework: &Homework) -> u64 {
-    homework.solve_cephalopod()
-}
+pub struct Day6;
+
+impl Solution for Day6 {
+    const DAY: usize = 6;
+    const TITLE: &'static str = "Trash Compactor";
+    // The numbers are aligned in columns, so the spaces at the end of the lines matter
+    const NORMALIZE: Normalize = Normalize { trailing_spaces: false, ..Normalize::ALL };
+    type Input = Homework;
+    type Output1 = u64;
+    type Output2 = u64;
+
+    fn part1(homework: &Homework) -> Result<u64, SolveError> {
+        Ok(homework.solve())
+    }
+
+    fn part2(homework: &Homework) -> Result<u64, SolveError> {
+        homework.solve_cephalopod()
+    }
 
-pub fn generator(input: &str) -> Homework {
-    Homework::new(input)
+    fn generator(input: &str) -> Result<Homework, ParseError> {
+        Homework::new(input)
+    }
 }
 
 pub struct Homework {
     operators: Vec<Operator>,
     operands: Vec<String>,
+    // the operands parsed as rows of numbers
+    numbers: Vec<Vec<u64>>,
 }
 
 impl Homework {
-    fn new(input: &str) -> Homework {
+    fn new(input: &str) -> Result<Homework, ParseError> {
         let mut lines = input.lines().rev();
-        let operators: Vec<Operator> = lines.next().unwrap()
+        let last = lines.next()
+            .ok_or_else(|| ParseError::new(Day6::DAY, input, input, "Missing the operators"))?;
+        let operators: Vec<Operator> = last
             .split_whitespace()
             .map(|o| match o {
-                "+" => Operator::Add,
-                "*" => Operator::Multiply,
-                _ => unreachable!(),
-            }).collect();
-        let operands = lines.rev()
+                "+" => Ok(Operator::Add),
+                "*" => Ok(Operator::Multiply),
+                _ => Err(ParseError::new(Day6::DAY, input, o, "Unknown operator")),
+            }).collect::<Result<_, _>>()?;
+        let lines: Vec<&str> = lines.rev().collect();
+        let numbers = lines.iter()
+            .map(|l| {
+                let row = l.split_whitespace()
+                    .map(|o| parse_token(Day6::DAY, input, o))
+                    .collect::<Result<Vec<u64>, _>>()?;
+                if row.len() != operators.len() {
+                    return Err(ParseError::new(Day6::DAY, input, l,
+                        format!("Expected {} operands", operators.len())));
+                }
+                Ok(row)
+            }).collect::<Result<_, _>>()?;
+        let operands = lines.iter()
             .map(|o| o.to_string())
             .collect();
 
-        Homework { operators, operands }
+        Ok(Homework { operators, operands, numbers })
     }
 
     fn solve(&self) -> u64 {
-        let operands: Vec<Vec<u64>> = self.operands.iter().map(|l| l.split_whitespace()
-            .map(|o| o.parse::<u64>().unwrap()).collect())
-            .collect();
-
         (0..self.operators.len()).map(|i| {
             match self.operators[i] {
-                Operator::Add => operands.iter().map(|r| r[i]).sum::<u64>(),
-                Operator::Multiply => operands.iter().map(|r| r[i]).product(),
+                Operator::Add => self.numbers.iter().map(|r| r[i]).sum::<u64>(),
+                Operator::Multiply => self.numbers.iter().map(|r| r[i]).product(),
             }
         }).sum::<u64>()
     }
 
-    fn solve_cephalopod(&self) -> u64 {
-        let width = self.operands.first().unwrap().chars().count();
+    fn solve_cephalopod(&self) -> Result<u64, SolveError> {
+        // Lines may be missing their trailing spaces
+        let width = self.operands.iter().map(|l| l.chars().count()).max().unwrap_or(0);
 
         let operands: Vec<Vec<u64>> = self.operands.iter()
             .map(|l| l.chars()
@@ -55,19 +82,23 @@ impl Homework {
             .collect();
 
         let operands: Vec<u64> = (0..width)
-            .map(|i| { operands.iter().map(move |r| r[i]) })
+            .map(|i| { operands.iter().map(move |r| r.get(i).copied().unwrap_or(0)) })
             .map(|c| {
                 c.filter(|v| v > &0).fold(0, |acc, x| (acc * 10) + x)
             }).collect();
         let operands: Vec<Vec<u64>> = operands.split(|v| v == &0).map(|i| i.to_vec())
             .collect();
+        if operands.len() != self.operators.len() {
+            return Err(SolveError::new(format!("Found {} problems for {} operators",
+                                               operands.len(), self.operators.len())));
+        }
 
-        (0..self.operators.len()).map(|i| {
+        Ok((0..self.operators.len()).map(|i| {
             match self.operators[i] {
                 Operator::Add => operands[i].iter().sum::<u64>(),
                 Operator::Multiply => operands[i].iter().product(),
             }
-        }).sum::<u64>()
+        }).sum::<u64>())
     }
 }
 
@@ -78,26 +109,30 @@ enum Operator {
 
 #[cfg(test)]
 mod tests {
-    use super::{generator, part1, part2};
+    use super::Day6;
+    use crate::utils::Solution;
 
     const INPUT: &str = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*   +   *   +  ";
 
     #[test]
     fn test_generator() {
-        let h = generator(INPUT);
+        let h = Day6::generator(INPUT).unwrap();
         assert_eq!(h.operators.len(), 4);
         assert_eq!(h.operands.len(), 3);
+
+        let e = Day6::generator("1 2\n3 4\n+ -").err().unwrap();
+        assert_eq!((e.line, e.column, e.text.as_str()), (3, 3, "-"));
     }
 
     #[test]
     fn test_part_1() {
-        let h = generator(INPUT);
-        assert_eq!(part1(&h), 4277556);
+        let h = Day6::generator(INPUT).unwrap();
+        assert_eq!(Day6::part1(&h).unwrap(), 4277556);
     }
 
     #[test]
     fn test_part_2() {
-        let h = generator(INPUT);
-        assert_eq!(part2(&h), 3263827);
+        let h = Day6::generator(INPUT).unwrap();
+        assert_eq!(Day6::part2(&h).unwrap(), 3263827);
     }
 }
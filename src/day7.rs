@@ -1,15 +1,26 @@
 use std::mem::swap;
+use crate::utils::{Grid, ParseError, Solution, SolveError};
 
-pub fn part1(manifold: &Lab) -> u64 {
-    manifold.active_splitters
-}
+pub struct Day7;
 
-pub fn part2(manifold: &Lab) -> u64 {
-    manifold.timelines
-}
+impl Solution for Day7 {
+    const DAY: usize = 7;
+    const TITLE: &'static str = "Laboratories";
+    type Input = Lab;
+    type Output1 = u64;
+    type Output2 = u64;
+
+    fn part1(manifold: &Lab) -> Result<u64, SolveError> {
+        Ok(manifold.active_splitters)
+    }
+
+    fn part2(manifold: &Lab) -> Result<u64, SolveError> {
+        Ok(manifold.timelines)
+    }
 
-pub fn generator(input: &str) -> Lab {
-    Lab::new(input)
+    fn generator(input: &str) -> Result<Lab, ParseError> {
+        Lab::new(input)
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -19,48 +30,44 @@ enum Space {
 }
 
 pub struct Lab {
-    start: (u64, u64),
-    manifold: Vec<Vec<Space>>,
+    // the (x, y) position of the S
+    start: (usize, usize),
+    manifold: Grid<Space>,
     timelines: u64,
     active_splitters: u64,
 }
 
 impl Lab {
-    fn width(&self) -> usize {
-        self.manifold[0].len()
-    }
-    fn new(input: &str) -> Self {
-        let mut start: Option<(u64, u64)> = None;
-        let manifold = input.trim().lines().enumerate()
-            .map(|(y, line)| line.chars().enumerate()
-                .map(|(x, char)| match char {
-                    '.' => Space::Empty,
-                    '^' => Space::Splitter,
-                    'S' => {
-                        start = Some((y as u64, x as u64));
-                        Space::Empty
-                    }
-                    _ => {
-                        unreachable!()
-                    }
-                }).collect()
-            ).collect();
-        let mut l = Lab { start: start.unwrap(), manifold, timelines: 0, active_splitters: 0 };
+    fn new(input: &str) -> Result<Self, ParseError> {
+        let mut start: Option<(usize, usize)> = None;
+        let manifold = Grid::parse(Day7::DAY, input, |pos, c| match c {
+            '.' => Some(Space::Empty),
+            '^' => Some(Space::Splitter),
+            'S' => {
+                start = Some(pos);
+                Some(Space::Empty)
+            }
+            _ => None,
+        })?;
+        let start = start.ok_or_else(|| ParseError::new(Day7::DAY, input, input,
+                                                        "Missing the start S"))?;
+        let mut l = Lab { start, manifold, timelines: 0, active_splitters: 0 };
         l.identify_timelines();
-        l
+        Ok(l)
     }
 
     fn identify_timelines(&mut self) {
-        let mut current = vec![0u64; self.width()];
-        let mut next = vec![0u64; self.width()];
-        current[self.start.1 as usize] += 1;
-        let width = self.width();
+        let width = self.manifold.width();
+        let mut current = vec![0u64; width];
+        let mut next = vec![0u64; width];
+        current[self.start.0] += 1;
         let mut active = Vec::new();
 
-        for y in (self.start.0 + 1)..self.manifold.len() as u64 {
+        for y in (self.start.1 + 1)..self.manifold.height() {
+            let row = self.manifold.row(y);
             for (x, v) in current.iter().enumerate().filter(|&(_, v)| v > &0) {
-                if self.manifold[y as usize][x] == Space::Splitter {
-                    active.push((y, x));
+                if row[x] == Space::Splitter {
+                    active.push((x, y));
                     if x > 0 {
                         next[x - 1] += v;
                     }
@@ -83,7 +90,8 @@ impl Lab {
 
 #[cfg(test)]
 mod tests {
-    use super::{generator, part1, part2};
+    use super::Day7;
+    use crate::utils::Solution;
 
     const INPUT: &str = ".......S.......
 ...............
@@ -104,21 +112,21 @@ mod tests {
 
     #[test]
     fn test_generator() {
-        let lab = generator(INPUT);
-        assert_eq!(lab.start, (0, 7));
-        assert_eq!(lab.manifold.len(), 16);
-        assert_eq!(lab.manifold.first().unwrap().len(), 15);
+        let lab = Day7::generator(INPUT).unwrap();
+        assert_eq!(lab.start, (7, 0));
+        assert_eq!(lab.manifold.height(), 16);
+        assert_eq!(lab.manifold.width(), 15);
     }
 
     #[test]
     fn test_part_1() {
-        let lab = generator(INPUT);
-        assert_eq!(part1(&lab), 21);
+        let lab = Day7::generator(INPUT).unwrap();
+        assert_eq!(Day7::part1(&lab).unwrap(), 21);
     }
 
     #[test]
     fn test_part_2() {
-        let lab = generator(INPUT);
-        assert_eq!(part2(&lab), 40);
+        let lab = Day7::generator(INPUT).unwrap();
+        assert_eq!(Day7::part2(&lab).unwrap(), 40);
     }
 }
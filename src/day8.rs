@@ -1,116 +1,302 @@
-use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use graphviz_rust::dot_structures::{Attribute, Edge, EdgeTy, Graph, Id, Node, NodeId, Stmt,
+                                    Subgraph, Vertex};
+use graphviz_rust::printer::{DotPrinter, PrinterContext};
+use crate::geometry::{parse_points, BoundingBox, KdTree, Point, Point3};
+use crate::utils::{DayError, DisjointSet, ParseError, Part, Solution, SolveError};
 
-pub fn part1(playground: &Playground) -> u64 {
-    playground.connect_boxes(Some(1000))
+pub struct Day8;
+
+impl Solution for Day8 {
+    const DAY: usize = 8;
+    const TITLE: &'static str = "Playground";
+    type Input = Playground;
+    type Output1 = u64;
+    type Output2 = u64;
+
+    fn part1(playground: &Playground) -> Result<u64, SolveError> {
+        playground.score_circuits(&playground.score)
+    }
+
+    fn part2(playground: &Playground) -> Result<u64, SolveError> {
+        let (idx1, idx2) = playground.last_connection()?;
+        Ok(playground.junction_boxes.point(idx1).x * playground.junction_boxes.point(idx2).x)
+    }
+
+    fn generator(input: &str) -> Result<Playground, ParseError> {
+        Playground::new(input)
+    }
+
+    /// The scoring for part 1 can be changed by the connections, top and
+    /// aggregate parameters.
+    fn configure(playground: &mut Playground, name: &str, value: &str) -> Result<(), SolveError> {
+        let invalid = |e: String| SolveError::new(format!("Invalid {name} {value:?}: {e}"));
+        let score = &mut playground.score;
+        match name {
+            "connections" => score.connections = value.parse().map_err(|e| invalid(format!("{e}")))?,
+            "top" => score.top = value.parse().map_err(|e| invalid(format!("{e}")))?,
+            "aggregate" => score.aggregate = value.parse().map_err(invalid)?,
+            _ => return Err(SolveError::new(format!("Unknown parameter {name}"))),
+        }
+        Ok(())
+    }
+}
+
+impl Day8 {
+    /// Draw the circuits for the input as a DOT graph with the boxes
+    /// clustered by the circuits after the given number of connections.
+    pub fn visualize(input: &str, connections: usize) -> Result<String, DayError> {
+        let playground = Self::generator(&Self::NORMALIZE.apply(input)).map_err(DayError::Parse)?;
+        playground.to_dot(connections).map_err(|e| DayError::Solve(Part::Two, e))
+    }
+}
+
+/// How the sizes of the largest circuits are combined.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Aggregate {
+    Product,
+    Sum,
+    Max,
+}
+
+impl Aggregate {
+    pub fn apply(&self, sizes: impl Iterator<Item = u64>) -> u64 {
+        match self {
+            Aggregate::Product => sizes.product(),
+            Aggregate::Sum => sizes.sum(),
+            Aggregate::Max => sizes.max().unwrap_or(0),
+        }
+    }
+}
+
+impl FromStr for Aggregate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "product" => Ok(Aggregate::Product),
+            "sum" => Ok(Aggregate::Sum),
+            "max" => Ok(Aggregate::Max),
+            _ => Err("expected product, sum or max".to_string()),
+        }
+    }
+}
+
+/// How part 1 scores the circuits: the boxes are joined by the given number
+/// of shortest connections, and then the sizes of the top largest circuits
+/// are aggregated. Boxes that weren't connected are circuits of 1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CircuitScore {
+    pub connections: usize,
+    pub top: usize,
+    pub aggregate: Aggregate,
+}
+
+impl Default for CircuitScore {
+    /// The scoring for the puzzle input.
+    fn default() -> Self {
+        CircuitScore { connections: 1000, top: 3, aggregate: Aggregate::Product }
+    }
+}
+
+/// An attribute for a DOT statement with a quoted value.
+fn attr(name: &str, value: impl std::fmt::Display) -> Attribute {
+    Attribute(Id::Plain(name.to_string()), Id::Escaped(format!("\"{value}\"")))
 }
 
-pub fn part2(playground: &Playground) -> u64 {
-    playground.connect_boxes(None)
+/// The DOT id of a junction box.
+fn box_id(i: usize) -> NodeId {
+    NodeId(Id::Plain(format!("box{i}")), None)
 }
 
-pub fn generator(input: &str) -> Playground {
-    Playground::new(input)
+/// The pairs of junction boxes as (index, index, squared distance) in order of
+/// increasing distance, which are found lazily rather than building all of
+/// the pairs up front. Each round uses the k-d tree to find the pairs within
+/// a radius, which doubles each round.
+struct Edges<'a> {
+    tree: &'a KdTree<Point3<u64>>,
+    // the squared radius of the next round
+    radius: u64,
+    // the squared radius that earlier rounds have covered
+    searched: Option<u64>,
+    // the squared distance between the farthest boxes, where the search stops
+    limit: u64,
+    // the pairs found by the last round that haven't been used, farthest first
+    pending: Vec<(usize, usize, u64)>,
 }
 
-#[derive(Debug, Eq, PartialEq)]
-struct Coord {
-    x: u64,
-    y: u64,
-    z: u64,
+impl<'a> Edges<'a> {
+    fn new(tree: &'a KdTree<Point3<u64>>) -> Self {
+        let boxes = tree.points();
+        let limit = BoundingBox::around(boxes).map_or(0, |b| b.min.distance_squared(&b.max));
+        // Start at the median distance from a box to its nearest neighbor
+        let mut nearest: Vec<u64> = boxes.iter()
+            .filter_map(|p| tree.nearest(p, 2).get(1).map(|n| n.1))
+            .collect();
+        let median = nearest.len() / 2;
+        let radius = match nearest.is_empty() {
+            true => 1,
+            false => *nearest.select_nth_unstable(median).1,
+        }.max(1);
+        Edges { tree, radius, searched: None, limit, pending: Vec::new() }
+    }
+
+    /// Find the pairs that are farther apart than the earlier rounds searched
+    /// and within the current radius.
+    fn search(&mut self) {
+        for (i, p) in self.tree.points().iter().enumerate() {
+            for (j, d) in self.tree.within(p, self.radius) {
+                if j > i && self.searched.is_none_or(|s| d > s) {
+                    self.pending.push((i, j, d));
+                }
+            }
+        }
+        self.pending.sort_unstable_by_key(|&(i, j, d)| std::cmp::Reverse((d, i, j)));
+        self.searched = Some(self.radius);
+        // Double the radius
+        self.radius *= 4;
+    }
 }
 
-impl Coord {
-    fn distance_between(&self, other: &Coord) -> u64 {
-        (self.x.abs_diff(other.x)).pow(2) + (self.y.abs_diff(other.y)).pow(2) + (self.z.abs_diff(other.z).pow(2))
+impl Iterator for Edges<'_> {
+    type Item = (usize, usize, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.searched.is_some_and(|s| s >= self.limit) {
+                return None;
+            }
+            self.search();
+        }
+        self.pending.pop()
     }
 }
 
 pub struct Playground {
-    junction_boxes: Vec<Coord>,
-    distances: Vec<(usize, usize, u64)>,
+    // the junction boxes in the order of the input
+    junction_boxes: KdTree<Point3<u64>>,
+    // how part 1 scores the circuits
+    score: CircuitScore,
 }
 
 impl Playground {
-    fn new(input: &str) -> Playground {
-        let junction_boxes = input.lines()
-            .map(|line| {
-                let mut values = line.split(',').map(|i| i.parse().unwrap());
-                let c = Coord { x: values.next().unwrap(), y: values.next().unwrap(), z: values.next().unwrap() };
-                match values.next() {
-                    Some(_) => unreachable!("Unexpected line with {line}"),
-                    None => c
-                }
-            }).collect();
-
-        let mut p = Playground { junction_boxes, distances: vec![] };
-        p.compute_distances();
-        p
-    }
-
-    fn compute_distances(&mut self) {
-        self.distances = self.junction_boxes.iter().enumerate().combinations(2)
-            .map(|combination| {
-                let (idx1, c1) = combination[0];
-                let (idx2, c2) = combination[1];
-                let d = c1.distance_between(&c2);
-                (idx1, idx2, d)
-            }).collect();
-        self.distances.sort_unstable_by(|item1, item2| item2.2.cmp(&item1.2));
-    }
-
-    fn connect_boxes(&self, times: Option<usize>) -> u64 {
-        let mut next_id = 0u32;
-        let mut connections: Vec<Option<u32>> = vec![None; self.junction_boxes.len()];
-        let mut distances = self.distances.clone();
-        let mut idx = 0usize;
-        let mut last_connection = None;
-
-        while times.is_none() || times.is_some_and(|v| idx < v) {
-            idx += 1;
-            let (idx_1, idx_2, _) = distances.pop().unwrap();
-            match (connections[idx_1], connections[idx_2]) {
-                (Some(c1), Some(c2)) if c1 == c2 => {
-                    continue;
-                }
-                (Some(c1), Some(c2)) => {
-                    connections.iter_mut()
-                        .filter(|item| item.is_some_and(|v| v == c2))
-                        .for_each(|item| *item = Some(c1));
-                }
-                (Some(c1), None) => {
-                    connections[idx_2] = Some(c1);
-                }
-                (None, Some(c2)) => {
-                    connections[idx_1] = Some(c2);
-                }
-                (None, None) => {
-                    connections[idx_1] = Some(next_id);
-                    connections[idx_2] = Some(next_id);
-                    next_id += 1;
-                }
+    fn new(input: &str) -> Result<Playground, ParseError> {
+        let junction_boxes: Vec<Point3<u64>> = parse_points(Day8::DAY, input)?;
+        Ok(Playground { junction_boxes: KdTree::new(&junction_boxes), score: Default::default() })
+    }
+
+    /// Use a different scoring for part 1, such as the one for the example.
+    pub fn with_score(self, score: CircuitScore) -> Self {
+        Playground { score, ..self }
+    }
+
+    /// The sizes of the circuits after making the given number of the
+    /// shortest connections, largest first.
+    pub fn circuit_sizes(&self, connections: usize) -> Result<Vec<usize>, SolveError> {
+        let mut circuits = DisjointSet::new(self.junction_boxes.len());
+        let mut distances = Edges::new(&self.junction_boxes);
+        for _ in 0..connections {
+            if circuits.components() == 1 {
+                break;
+            }
+            let (idx_1, idx_2, _) = distances.next()
+                .ok_or_else(|| SolveError::new("Ran out of junction boxes to connect"))?;
+            circuits.union(idx_1, idx_2);
+        }
+        let mut sizes = circuits.component_sizes();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(sizes)
+    }
+
+    /// Aggregate the sizes of the largest circuits.
+    pub fn score_circuits(&self, score: &CircuitScore) -> Result<u64, SolveError> {
+        let sizes = self.circuit_sizes(score.connections)?;
+        Ok(score.aggregate.apply(sizes.iter().take(score.top).map(|&s| s as u64)))
+    }
+
+    /// The indexes of the boxes whose connection joins all of the boxes into
+    /// one circuit.
+    pub fn last_connection(&self) -> Result<(usize, usize), SolveError> {
+        let mut circuits = DisjointSet::new(self.junction_boxes.len());
+        for (idx_1, idx_2, _) in Edges::new(&self.junction_boxes) {
+            if circuits.union(idx_1, idx_2) && circuits.components() == 1 {
+                return Ok((idx_1, idx_2));
+            }
+        }
+        Err(SolveError::new("The junction boxes were never connected"))
+    }
+
+    /// Draw the connections as a DOT graph, connecting the boxes until they
+    /// form one circuit. The boxes are clustered by the circuits that the
+    /// first connections made, each edge is labelled with its order and
+    /// length, and the edge that completes the circuit is highlighted.
+    pub fn to_dot(&self, connections: usize) -> Result<String, SolveError> {
+        let mut circuits = DisjointSet::new(self.junction_boxes.len());
+        let mut clusters = None;
+        let mut edges = Vec::new();
+        for (order, (i, j, d)) in Edges::new(&self.junction_boxes).enumerate() {
+            if order == connections {
+                clusters = Some(circuits.clone());
+            }
+            let joined = circuits.union(i, j);
+            let mut attributes = vec![
+                attr("label", format!("#{} {:.1}", order + 1, (d as f64).sqrt())),
+            ];
+            let last = joined && circuits.components() == 1;
+            if last {
+                attributes.extend([attr("color", "red"), attr("penwidth", 3)]);
             }
-            if connections.iter().all_equal() {
-                last_connection = Some((idx_1, idx_2));
+            edges.push(Stmt::Edge(Edge {
+                ty: EdgeTy::Pair(Vertex::N(box_id(i)), Vertex::N(box_id(j))),
+                attributes,
+            }));
+            if last {
                 break;
             }
         }
-        if times.is_some() {
-            let mut circuit_lengths = vec![0; next_id as usize];
-            connections.iter().filter(|item| item.is_some())
-                .for_each(|item| {circuit_lengths[item.unwrap() as usize] += 1;});
-            circuit_lengths.sort_unstable();
-            circuit_lengths.iter().rev().take(3)
-                .product::<u64>()
-        } else {
-            let (idx1, idx2) = last_connection.unwrap();
-            self.junction_boxes[idx1].x * self.junction_boxes[idx2].x
+        if circuits.components() > 1 {
+            return Err(SolveError::new("The junction boxes were never connected"));
+        }
+        // Fewer connections than requested leaves them all in one circuit
+        let mut clusters = clusters.unwrap_or(circuits);
+
+        let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..self.junction_boxes.len() {
+            members.entry(clusters.find(i)).or_default().push(i);
+        }
+        let mut stmts = Vec::new();
+        let mut cluster_count = 0;
+        for boxes in members.values() {
+            let nodes = boxes.iter().map(|&i| {
+                let p = self.junction_boxes.point(i);
+                let label = attr("label", format!("{},{},{}", p.x, p.y, p.z));
+                Stmt::Node(Node::new(box_id(i), vec![label]))
+            });
+            if boxes.len() == 1 {
+                stmts.extend(nodes);
+            } else {
+                let label = attr("label", format!("{} boxes", boxes.len()));
+                let id = Id::Plain(format!("cluster_{cluster_count}"));
+                cluster_count += 1;
+                stmts.push(Stmt::Subgraph(Subgraph {
+                    id,
+                    stmts: std::iter::once(Stmt::Attribute(label)).chain(nodes).collect(),
+                }));
+            }
         }
+        stmts.extend(edges);
+        let graph = Graph::Graph { id: Id::Plain("playground".to_string()), strict: false, stmts };
+        Ok(graph.print(&mut PrinterContext::default()))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{generator, part2, Coord};
+    use super::{Aggregate, CircuitScore, Day8, Edges};
+    use crate::geometry::{KdTree, Point, Point3};
+    use itertools::Itertools;
+    use crate::utils::Solution;
 
     const INPUT: &str = "162,817,812
 57,618,57
@@ -133,23 +319,84 @@ mod tests {
 984,92,344
 425,690,689";
 
+    /// The example only makes 10 connections.
+    const EXAMPLE_SCORE: CircuitScore =
+        CircuitScore { connections: 10, top: 3, aggregate: Aggregate::Product };
+
     #[test]
     fn test_generator() {
-        let p = generator(INPUT);
+        let p = Day8::generator(INPUT).unwrap();
         assert_eq!(p.junction_boxes.len(), 20);
-        assert_eq!(p.junction_boxes[0], Coord { x: 162, y: 817, z: 812 });
-        assert_eq!(p.junction_boxes.last().unwrap(), &Coord { x: 425, y: 690, z: 689 });
+        assert_eq!(p.junction_boxes.point(0), &Point3::new(162, 817, 812));
+        assert_eq!(p.junction_boxes.points().last().unwrap(), &Point3::new(425, 690, 689));
+
+        let e = Day8::generator("1,2,3\n4,5").err().unwrap();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 1, "4,5"));
+    }
+
+    #[test]
+    fn test_edges() {
+        let p = Day8::generator(INPUT).unwrap();
+        let boxes = p.junction_boxes.points();
+        let mut expected: Vec<u64> = boxes.iter().tuple_combinations()
+            .map(|(a, b)| a.distance_squared(b))
+            .collect();
+        expected.sort_unstable();
+        let edges: Vec<_> = Edges::new(&p.junction_boxes).collect();
+        assert_eq!(edges.iter().map(|e| e.2).collect::<Vec<_>>(), expected);
+        assert!(edges.iter().all(|&(i, j, d)| {
+            i < j && boxes[i].distance_squared(&boxes[j]) == d
+        }));
+        assert_eq!(Edges::new(&KdTree::new(&boxes[..1])).count(), 0);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let p = Day8::generator(INPUT).unwrap();
+        let dot = p.to_dot(10).unwrap();
+        assert!(dot.starts_with("graph playground {"));
+        // The circuits of 5, 4, 2 and 2 boxes after 10 connections
+        assert_eq!(dot.matches("subgraph cluster_").count(), 4);
+        assert!(dot.contains("label=\"5 boxes\""));
+        assert!(dot.contains("box0 -- box19 [label=\"#1 316.9\"]"));
+        assert_eq!(dot.matches("color=\"red\"").count(), 1);
     }
 
     #[test]
     fn test_part_1() {
-        let p = generator(INPUT);
-        assert_eq!(p.connect_boxes(Some(10)), 40);
+        let p = Day8::generator(INPUT).unwrap();
+        assert_eq!(Day8::part1(&p.with_score(EXAMPLE_SCORE)).unwrap(), 40);
+    }
+
+    #[test]
+    fn test_score_circuits() {
+        let p = Day8::generator(INPUT).unwrap();
+        assert_eq!(p.circuit_sizes(10).unwrap()[..5], [5, 4, 2, 2, 1]);
+        let score = |top, aggregate| CircuitScore { connections: 10, top, aggregate };
+        assert_eq!(p.score_circuits(&score(3, Aggregate::Sum)).unwrap(), 11);
+        assert_eq!(p.score_circuits(&score(4, Aggregate::Product)).unwrap(), 80);
+        assert_eq!(p.score_circuits(&score(3, Aggregate::Max)).unwrap(), 5);
+        // every box is in one circuit long before 1000 connections
+        assert_eq!(p.score_circuits(&CircuitScore::default()).unwrap(), 20);
+        let single = Day8::generator("1,2,3").unwrap();
+        assert_eq!(single.circuit_sizes(1000).unwrap(), [1]);
+        assert_eq!(Day8::part1(&single).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_configure() {
+        let mut p = Day8::generator(INPUT).unwrap();
+        Day8::configure(&mut p, "connections", "10").unwrap();
+        Day8::configure(&mut p, "aggregate", "sum").unwrap();
+        assert_eq!(Day8::part1(&p).unwrap(), 11);
+        assert!(Day8::configure(&mut p, "top", "three").is_err());
+        assert!(Day8::configure(&mut p, "aggregate", "min").is_err());
+        assert!(Day8::configure(&mut p, "radius", "1").is_err());
     }
 
     #[test]
     fn test_part_2() {
-        let p = generator(INPUT);
-        assert_eq!(part2(&p), 25272);
+        let p = Day8::generator(INPUT).unwrap();
+        assert_eq!(Day8::part2(&p).unwrap(), 25272);
     }
 }
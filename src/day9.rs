@@ -1,36 +1,53 @@
 use std::collections::{HashMap};
 use itertools::Itertools;
+use crate::geometry::{parse_points, BoundingBox, Point2};
+use crate::utils::{ParseError, Solution, SolveError};
 
-pub fn part1(floor: &Floor) -> u64 {
-    floor.find_largest_area()
-}
+pub struct Day9;
 
-pub fn part2(floor: &Floor) -> u64 {
-    floor.red_tiles.iter().combinations(2)
-        .filter(|p| p[0].x != p[1].x || p[0].y != p[1].y)
-        .map(|p| floor.compute_enclosed_area(p[0], p[1]))
-        .max().unwrap()
-}
+impl Solution for Day9 {
+    const DAY: usize = 9;
+    const TITLE: &'static str = "Movie Theater";
+    type Input = Floor;
+    type Output1 = u64;
+    type Output2 = u64;
+
+    fn part1(floor: &Floor) -> Result<u64, SolveError> {
+        floor.find_largest_area()
+            .ok_or_else(|| SolveError::new("Need at least two red tiles"))
+    }
+
+    fn part2(floor: &Floor) -> Result<u64, SolveError> {
+        floor.red_tiles.iter().combinations(2)
+            .filter(|p| p[0].x != p[1].x || p[0].y != p[1].y)
+            .map(|p| floor.compute_enclosed_area(p[0], p[1]))
+            .max()
+            .ok_or_else(|| SolveError::new("Need at least two distinct red tiles"))
+    }
 
-pub fn generator(input: &str) -> Floor {
-    Floor::new(input)
+    fn generator(input: &str) -> Result<Floor, ParseError> {
+        Floor::new(input)
+    }
 }
 
+/// Map from a pair of adjacent coordinate values to the sorted positions of the
+/// edges crossing them.
+type Lines = HashMap<(u64, u64), Vec<u64>>;
+
+type Coord = Point2<u64>;
+
 pub struct Floor {
     red_tiles: Vec<Coord>,
     x_values: Vec<u64>,
     y_values: Vec<u64>,
-    x_lines: HashMap<(u64, u64), Vec<u64>>,
-    y_lines: HashMap<(u64, u64), Vec<u64>>,
+    x_lines: Lines,
+    y_lines: Lines,
 }
 
 impl Floor {
-    fn new(input: &str) -> Floor {
-        let red_tiles: Vec<Coord> = input.lines()
-            .map(|line| {
-                let (x, y) = line.split_once(',').unwrap();
-                Coord::new(x.parse().unwrap(), y.parse().unwrap())
-            }).collect();
+    fn new(input: &str) -> Result<Floor, ParseError> {
+        let red_tiles: Vec<Coord> = parse_points(Day9::DAY, input)?;
+        Floor::check_loop(input, &red_tiles)?;
         let x_values: Vec<u64> = red_tiles.iter().map(|coord| coord.x)
             .sorted_unstable()
             .dedup().collect();
@@ -39,7 +56,24 @@ impl Floor {
             .dedup().collect();
 
         let (x_lines, y_lines) = Floor::make_lines(&red_tiles, &x_values, &y_values);
-        Floor { red_tiles, x_values, y_values, x_lines, y_lines }
+        Ok(Floor { red_tiles, x_values, y_values, x_lines, y_lines })
+    }
+
+    /// Each red tile must be joined to the next one, and the last one to the
+    /// first, by a straight row or column of green tiles.
+    fn check_loop(input: &str, red_tiles: &[Coord]) -> Result<(), ParseError> {
+        let lines: Vec<&str> = input.lines().collect();
+        for (idx, (a, b)) in red_tiles.iter().circular_tuple_windows().enumerate() {
+            if a.x != b.x && a.y != b.y {
+                return Err(match lines.get(idx + 1) {
+                    Some(line) => ParseError::new(Day9::DAY, input, line,
+                                                  "Not in the same row or column as the previous red tile"),
+                    None => ParseError::new(Day9::DAY, input, lines[idx],
+                                            "Not in the same row or column as the first red tile"),
+                });
+            }
+        }
+        Ok(())
     }
 
     fn is_xline_enclosing(&self, x_pos: &(usize, usize), y: &(u64, u64)) -> bool {
@@ -50,7 +84,7 @@ impl Floor {
         Floor::is_line_enclosing(y_pos, x, &self.x_lines, &self.y_values)
     }
 
-    fn is_line_enclosing(pos: &(usize, usize), value: &(u64, u64), lines: &HashMap<(u64, u64), Vec<u64>>, values: &[u64]) -> bool {
+    fn is_line_enclosing(pos: &(usize, usize), value: &(u64, u64), lines: &Lines, values: &[u64]) -> bool {
         for v in values[pos.0..=pos.1].windows(2) {
             let search_space = lines.get(&(v[0], v[1])).unwrap();
             let min_pos = match search_space.binary_search(&value.0) {
@@ -68,15 +102,15 @@ impl Floor {
         true
     }
 
-    fn find_largest_area(&self) -> u64 {
+    fn find_largest_area(&self) -> Option<u64> {
         self.red_tiles.iter().combinations(2)
-            .map(|combination| combination[0].area(combination[1]))
-            .max().unwrap()
+            .map(|combination| BoundingBox::from_corners(combination[0], combination[1]).area())
+            .max()
     }
 
-    fn make_lines(red_tiles: &[Coord], x_values: &[u64], y_values: &[u64]) -> (HashMap<(u64, u64), Vec<u64>>, HashMap<(u64, u64), Vec<u64>>) {
-        let mut x_lines: HashMap<(u64, u64), Vec<u64>> = HashMap::new();
-        let mut y_lines: HashMap<(u64, u64), Vec<u64>> = HashMap::new();
+    fn make_lines(red_tiles: &[Coord], x_values: &[u64], y_values: &[u64]) -> (Lines, Lines) {
+        let mut x_lines: Lines = HashMap::new();
+        let mut y_lines: Lines = HashMap::new();
 
         red_tiles.iter().circular_tuple_windows()
             .filter(|(a, b)| a.x == b.x)
@@ -94,7 +128,7 @@ impl Floor {
         (x_lines, y_lines)
     }
 
-    fn compute_lines(v1: &u64, v2: &u64, o: &u64, values: &[u64], lines: &mut HashMap<(u64, u64), Vec<u64>>) {
+    fn compute_lines(v1: &u64, v2: &u64, o: &u64, values: &[u64], lines: &mut Lines) {
         let r = v1.min(v2)..=v1.max(v2);
 
         values.iter()
@@ -108,42 +142,26 @@ impl Floor {
     }
 
     fn compute_enclosed_area(&self, p1: &Coord, p2: &Coord) -> u64 {
-        let x = (p1.x.min(p2.x), p1.x.max(p2.x));
-        let y = (p1.y.min(p2.y), p1.y.max(p2.y));
+        let bounds = BoundingBox::from_corners(p1, p2);
+        let x = (bounds.min.x, bounds.max.x);
+        let y = (bounds.min.y, bounds.max.y);
         let x_pos = (self.x_values.binary_search(&x.0).unwrap(),
                      self.x_values.binary_search(&x.1).unwrap());
         let y_pos = (self.y_values.binary_search(&y.0).unwrap(),
                      self.y_values.binary_search(&y.1).unwrap());
         if self.is_xline_enclosing(&x_pos, &y)
             && self.is_yline_enclosing(&y_pos, &x) {
-            (x.1 - x.0 + 1) * (y.1 - y.0 + 1)
+            bounds.area()
         } else {
             0
         }
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-#[derive(Hash)]
-#[derive(Clone)]
-struct Coord {
-    x: u64,
-    y: u64,
-}
-
-impl Coord {
-    fn new(x: u64, y: u64) -> Coord {
-        Coord { x, y }
-    }
-
-    fn area(&self, other: &Coord) -> u64 {
-        (self.x.abs_diff(other.x) + 1) * (self.y.abs_diff(other.y) + 1)
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use super::{generator, part1, part2, Coord};
+    use super::{Day9, Coord};
+    use crate::utils::Solution;
 
     const INPUT: &str = "7,1
 11,1
@@ -156,19 +174,27 @@ mod tests {
 
     #[test]
     fn test_generator() {
-        let f = generator(INPUT);
+        let f = Day9::generator(INPUT).unwrap();
         assert_eq!(f.red_tiles.len(), 8);
     }
 
+    #[test]
+    fn test_generator_rejects_diagonals() {
+        let e = Day9::generator("1,1\n5,5\n9,2\n").err().unwrap();
+        assert_eq!((e.line, e.column), (2, 1));
+        let e = Day9::generator("1,1\n1,5\n9,5\n").err().unwrap();
+        assert_eq!((e.line, e.text.as_str()), (3, "9,5"));
+    }
+
     #[test]
     fn test_part_1() {
-        let f = generator(INPUT);
-        assert_eq!(part1(&f), 50);
+        let f = Day9::generator(INPUT).unwrap();
+        assert_eq!(Day9::part1(&f).unwrap(), 50);
     }
 
     #[test]
     fn test_make_lines() {
-        let f = generator(INPUT);
+        let f = Day9::generator(INPUT).unwrap();
         assert_eq!(f.x_lines.values().map(|v| v.len()).sum::<usize>(), 6);
         assert_eq!(f.y_lines.values().map(|v| v.len()).sum::<usize>(), 6);
     }
@@ -176,15 +202,15 @@ mod tests {
     #[test]
     fn test_enclosed_area() {
         // (7,1) -> (9,5)
-        let f = generator(INPUT);
-        let c1 = Coord { x: 7, y: 1 };
-        let c2 = Coord { x: 9, y: 5 };
+        let f = Day9::generator(INPUT).unwrap();
+        let c1 = Coord::new(7, 1);
+        let c2 = Coord::new(9, 5);
         assert_eq!(f.compute_enclosed_area(&c1, &c2), 15);
     }
 
     #[test]
     fn test_part_2() {
-        let f = generator(INPUT);
-        assert_eq!(part2(&f), 24);
+        let f = Day9::generator(INPUT).unwrap();
+        assert_eq!(Day9::part2(&f).unwrap(), 24);
     }
 }
\ No newline at end of file
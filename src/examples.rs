@@ -0,0 +1,160 @@
+use crate::utils::{DayResult, DaySolution, Params, Part};
+use colored::Colorize;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+/// The expected answers for the examples, which is read from answers.yml
+/// in the examples directory. Parts that an example doesn't cover are null.
+#[derive(Default, Deserialize)]
+struct Manifest {
+  // map from day name to example name to answers
+  days: BTreeMap<String, BTreeMap<String, Entry>>,
+}
+
+/// An example's entry in the manifest, which is either its answers or its
+/// answers and the parameters that the puzzle gives for the example.
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum Entry {
+  Answers(Vec<Option<String>>),
+  Configured {
+    answers: Vec<Option<String>>,
+    #[serde(default)]
+    params: Params,
+  },
+}
+
+/// A puzzle example from {dir}/dayN/NAME.txt with its known answers.
+pub struct Example {
+  pub day: &'static dyn DaySolution,
+  pub name: String,
+  pub input: String,
+  pub expected: Vec<Option<String>>,
+  /// the parameters that the day is run with
+  pub params: Params,
+}
+
+impl Example {
+  /// Load the examples for the given days. Days without a directory have no
+  /// examples and examples without an entry in the manifest are run
+  /// without being checked.
+  pub fn load(dir: &str, days: &[&'static dyn DaySolution]) -> io::Result<Vec<Example>> {
+    let manifest: Manifest = match fs::File::open(Path::new(dir).join("answers.yml")) {
+      Ok(f) => serde_yaml::from_reader(f).map_err(io::Error::other)?,
+      Err(_) => Manifest::default(),
+    };
+    let mut result = Vec::new();
+    for &day in days {
+      let day_dir = Path::new(dir).join(day.name());
+      if !day_dir.is_dir() {
+        continue;
+      }
+      let mut files: Vec<_> = fs::read_dir(day_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+      files.retain(|p| p.extension().is_some_and(|e| e == "txt"));
+      files.sort();
+      for file in files {
+        let name = file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let (expected, params) = match manifest.days.get(&day.name())
+          .and_then(|examples| examples.get(&name)) {
+          Some(Entry::Answers(answers)) => (answers.clone(), Params::new()),
+          Some(Entry::Configured { answers, params }) => (answers.clone(), params.clone()),
+          None => Default::default(),
+        };
+        let input = fs::read_to_string(&file)?;
+        result.push(Example { day, name, input, expected, params });
+      }
+    }
+    Ok(result)
+  }
+
+  fn expected(&self, part: Part) -> Option<&String> {
+    let idx = Part::ALL.iter().position(|p| *p == part)?;
+    self.expected.get(idx)?.as_ref()
+  }
+
+  /// Run the example for the requested parts that have an expected answer,
+  /// or all of the requested parts if none of them do.
+  pub fn run(&self, parts: &[Part]) -> ExampleRun {
+    let mut checked: Vec<Part> = parts.iter().copied()
+      .filter(|p| self.expected(*p).is_some())
+      .collect();
+    if checked.is_empty() {
+      checked = parts.to_vec();
+    }
+    let result = self.day.run_configured(&self.input, &checked, &self.params);
+    let outcomes = checked.iter()
+      .map(|&part| {
+        let actual = result.part(part).1.as_ref();
+        let outcome = match self.expected(part) {
+          None => Outcome::Unchecked,
+          Some(e) if Some(e) == actual => Outcome::Pass,
+          Some(_) => Outcome::Fail,
+        };
+        (part, outcome)
+      }).collect();
+    ExampleRun { name: format!("{} {}", result.pretty_day(), self.name), result, outcomes }
+  }
+}
+
+/// How a part of an example compared to its expected answer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outcome {
+  Pass,
+  Fail,
+  /// There is no expected answer for the part.
+  Unchecked,
+}
+
+/// The result of running an example.
+pub struct ExampleRun {
+  pub name: String,
+  pub result: DayResult,
+  pub outcomes: Vec<(Part, Outcome)>,
+}
+
+impl ExampleRun {
+  pub fn passed(&self) -> bool {
+    self.result.error.is_none() && self.outcomes.iter().all(|(_, o)| *o != Outcome::Fail)
+  }
+}
+
+impl fmt::Display for ExampleRun {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.name.bold())?;
+    for (part, outcome) in &self.outcomes {
+      let answer = self.result.part(*part).1.as_deref().unwrap_or("-");
+      let status = match outcome {
+        Outcome::Pass => "pass".green(),
+        Outcome::Fail => "FAIL".red().bold(),
+        Outcome::Unchecked => "unchecked".yellow(),
+      };
+      write!(f, " · Part {part} {answer} {status}")?;
+    }
+    if let Some(e) = &self.result.error {
+      write!(f, " · {} {}", "Failed".red().bold(), e)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Example;
+  use crate::utils::Part;
+  use crate::DAYS;
+
+  #[test]
+  fn test_examples() {
+    let days: Vec<_> = DAYS.iter().collect();
+    let examples = Example::load("examples", &days).unwrap();
+    assert!(examples.len() >= days.len());
+    for example in &examples {
+      let run = example.run(&Part::ALL);
+      assert!(run.passed(), "{run}");
+    }
+  }
+}
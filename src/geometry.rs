@@ -0,0 +1,362 @@
+use crate::utils::{parse_token, ParseError};
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+/// The numeric types that can be used for the coordinates of a point.
+pub trait Coordinate: Copy + Default + Ord + fmt::Debug + From<u8>
+  + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + FromStr<Err: fmt::Display> {
+  /// The distance between two values, which works for unsigned types too.
+  fn distance_to(self, other: Self) -> Self {
+    self.max(other) - self.min(other)
+  }
+}
+
+impl<T> Coordinate for T where T: Copy + Default + Ord + fmt::Debug + From<u8>
+  + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + FromStr<Err: fmt::Display> {}
+
+/// A point with a coordinate for each axis.
+pub trait Point: Copy + Eq {
+  type Coord: Coordinate;
+  /// The number of axes.
+  const AXES: usize;
+
+  /// The coordinate on the given axis, which is less than AXES.
+  fn axis(&self, axis: usize) -> Self::Coord;
+
+  /// Combine the coordinates of two points axis by axis.
+  fn zip_with(&self, other: &Self, f: impl Fn(Self::Coord, Self::Coord) -> Self::Coord) -> Self;
+
+  /// Create the point from the coordinates of each axis, or None if there is
+  /// the wrong number of them.
+  fn from_axes(axes: &[Self::Coord]) -> Option<Self>;
+
+  /// The distance along each axis.
+  fn axis_distances(&self, other: &Self) -> impl Iterator<Item = Self::Coord> {
+    (0..Self::AXES).map(|i| self.axis(i).distance_to(other.axis(i)))
+  }
+
+  /// The sum of the distances along each axis.
+  fn manhattan(&self, other: &Self) -> Self::Coord {
+    self.axis_distances(other).fold(Self::Coord::default(), |sum, d| sum + d)
+  }
+
+  /// The largest of the distances along each axis.
+  fn chebyshev(&self, other: &Self) -> Self::Coord {
+    self.axis_distances(other).max().unwrap_or_default()
+  }
+
+  /// The square of the straight line distance, which orders points the same
+  /// way as the distance without needing a square root.
+  fn distance_squared(&self, other: &Self) -> Self::Coord {
+    self.axis_distances(other).fold(Self::Coord::default(), |sum, d| sum + d * d)
+  }
+
+  /// Parse a point from a line of comma separated coordinates, such as "1,2,3".
+  fn parse(day: usize, input: &str, line: &str) -> Result<Self, ParseError> {
+    let axes = line.split(',')
+      .map(|token| parse_token(day, input, token.trim()))
+      .collect::<Result<Vec<_>, _>>()?;
+    Self::from_axes(&axes).ok_or_else(|| {
+      let names = ["x", "y", "z"];
+      ParseError::new(day, input, line, format!("Expected {}", names[..Self::AXES].join(",")))
+    })
+  }
+}
+
+/// Parse a point from each line of the input.
+pub fn parse_points<P: Point>(day: usize, input: &str) -> Result<Vec<P>, ParseError> {
+  input.lines().map(|line| P::parse(day, input, line)).collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Point2<T> {
+  pub x: T,
+  pub y: T,
+}
+
+impl<T> Point2<T> {
+  pub const fn new(x: T, y: T) -> Self {
+    Point2 { x, y }
+  }
+}
+
+impl<T: Coordinate> Point for Point2<T> {
+  type Coord = T;
+  const AXES: usize = 2;
+
+  fn axis(&self, axis: usize) -> T {
+    [self.x, self.y][axis]
+  }
+
+  fn zip_with(&self, other: &Self, f: impl Fn(T, T) -> T) -> Self {
+    Point2::new(f(self.x, other.x), f(self.y, other.y))
+  }
+
+  fn from_axes(axes: &[T]) -> Option<Self> {
+    match *axes {
+      [x, y] => Some(Point2::new(x, y)),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Point3<T> {
+  pub x: T,
+  pub y: T,
+  pub z: T,
+}
+
+impl<T> Point3<T> {
+  pub const fn new(x: T, y: T, z: T) -> Self {
+    Point3 { x, y, z }
+  }
+}
+
+impl<T: Coordinate> Point for Point3<T> {
+  type Coord = T;
+  const AXES: usize = 3;
+
+  fn axis(&self, axis: usize) -> T {
+    [self.x, self.y, self.z][axis]
+  }
+
+  fn zip_with(&self, other: &Self, f: impl Fn(T, T) -> T) -> Self {
+    Point3::new(f(self.x, other.x), f(self.y, other.y), f(self.z, other.z))
+  }
+
+  fn from_axes(axes: &[T]) -> Option<Self> {
+    match *axes {
+      [x, y, z] => Some(Point3::new(x, y, z)),
+      _ => None,
+    }
+  }
+}
+
+/// An axis aligned box that includes both of its corners, so a box from a
+/// point to itself has a size of 1 on each axis.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BoundingBox<P> {
+  pub min: P,
+  pub max: P,
+}
+
+impl<P: Point> BoundingBox<P> {
+  /// The box with the two points at opposite corners.
+  pub fn from_corners(a: &P, b: &P) -> Self {
+    BoundingBox { min: a.zip_with(b, Ord::min), max: a.zip_with(b, Ord::max) }
+  }
+
+  /// The smallest box that contains all of the points, or None if there are
+  /// no points.
+  pub fn around<'a>(points: impl IntoIterator<Item = &'a P>) -> Option<Self> where P: 'a {
+    points.into_iter().fold(None, |bounds, p| Some(match bounds {
+      None => BoundingBox { min: *p, max: *p },
+      Some(b) => BoundingBox {
+        min: b.min.zip_with(p, Ord::min),
+        max: b.max.zip_with(p, Ord::max),
+      },
+    }))
+  }
+
+  pub fn contains(&self, p: &P) -> bool {
+    (0..P::AXES).all(|i| self.min.axis(i) <= p.axis(i) && p.axis(i) <= self.max.axis(i))
+  }
+
+  /// The number of coordinates that the box covers on the axis.
+  pub fn size(&self, axis: usize) -> P::Coord {
+    self.max.axis(axis) - self.min.axis(axis) + P::Coord::from(1)
+  }
+}
+
+impl<T: Coordinate> BoundingBox<Point2<T>> {
+  /// The number of points inside the box, including its edges.
+  pub fn area(&self) -> T {
+    self.size(0) * self.size(1)
+  }
+}
+
+impl<T: Coordinate> BoundingBox<Point3<T>> {
+  /// The number of points inside the box, including its faces.
+  pub fn volume(&self) -> T {
+    self.size(0) * self.size(1) * self.size(2)
+  }
+}
+
+/// A k-d tree over a set of points for finding the nearest points to a target
+/// and the points within a distance of it. The tree is stored implicitly: the
+/// median of each range of the order is the node that splits it, cycling
+/// through the axes with the depth.
+pub struct KdTree<P> {
+  points: Vec<P>,
+  // the indexes of the points arranged as the tree
+  order: Vec<usize>,
+}
+
+impl<P: Point> KdTree<P> {
+  /// Build the tree in O(n log n).
+  pub fn new(points: &[P]) -> Self {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    Self::build(points, &mut order, 0);
+    KdTree { points: points.to_vec(), order }
+  }
+
+  fn build(points: &[P], order: &mut [usize], depth: usize) {
+    if order.len() <= 1 {
+      return;
+    }
+    let axis = depth % P::AXES;
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by_key(mid, |&i| points[i].axis(axis));
+    let (left, right) = order.split_at_mut(mid);
+    Self::build(points, left, depth + 1);
+    Self::build(points, &mut right[1..], depth + 1);
+  }
+
+  pub fn len(&self) -> usize {
+    self.points.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.points.is_empty()
+  }
+
+  /// The points that the tree was built from in their original order.
+  pub fn points(&self) -> &[P] {
+    &self.points
+  }
+
+  /// The point with the given index in the points the tree was built from.
+  pub fn point(&self, index: usize) -> &P {
+    &self.points[index]
+  }
+
+  /// Visit the nodes that could be within the bound on the squared distance.
+  /// Visiting a node returns the new bound, which is None when every node
+  /// has to be visited.
+  fn search(&self, target: &P, range: (usize, usize), depth: usize, bound: &mut Option<P::Coord>,
+            visit: &mut dyn FnMut(usize, P::Coord) -> Option<P::Coord>) {
+    let (lo, hi) = range;
+    if lo >= hi {
+      return;
+    }
+    let mid = (lo + hi) / 2;
+    let index = self.order[mid];
+    let node = &self.points[index];
+    *bound = visit(index, target.distance_squared(node));
+
+    let axis = depth % P::AXES;
+    let (near, far) = match target.axis(axis) < node.axis(axis) {
+      true => ((lo, mid), (mid + 1, hi)),
+      false => ((mid + 1, hi), (lo, mid)),
+    };
+    self.search(target, near, depth + 1, bound, visit);
+    let gap = target.axis(axis).distance_to(node.axis(axis));
+    if bound.is_none_or(|b| gap * gap <= b) {
+      self.search(target, far, depth + 1, bound, visit);
+    }
+  }
+
+  /// The k nearest points to the target as (index, squared distance), nearest
+  /// first. A point at the target is included.
+  pub fn nearest(&self, target: &P, k: usize) -> Vec<(usize, P::Coord)> {
+    if k == 0 {
+      return Vec::new();
+    }
+    // The farthest of the nearest points found so far is at the top
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    self.search(target, (0, self.order.len()), 0, &mut None, &mut |index, d| {
+      heap.push((d, index));
+      if heap.len() > k {
+        heap.pop();
+      }
+      if heap.len() < k { None } else { heap.peek().map(|&(d, _)| d) }
+    });
+    heap.into_sorted_vec().into_iter().map(|(d, index)| (index, d)).collect()
+  }
+
+  /// The points within the squared distance of the target as (index, squared
+  /// distance) in no particular order.
+  pub fn within(&self, target: &P, distance_squared: P::Coord) -> Vec<(usize, P::Coord)> {
+    let mut found = Vec::new();
+    self.search(target, (0, self.order.len()), 0, &mut None, &mut |index, d| {
+      if d <= distance_squared {
+        found.push((index, d));
+      }
+      Some(distance_squared)
+    });
+    found
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{parse_points, BoundingBox, KdTree, Point, Point2, Point3};
+
+  #[test]
+  fn test_distances() {
+    let (a, b) = (Point3::new(1u64, 5, 2), Point3::new(4, 1, 2));
+    assert_eq!(a.manhattan(&b), 7);
+    assert_eq!(a.chebyshev(&b), 4);
+    assert_eq!(a.distance_squared(&b), 25);
+    let (a, b) = (Point2::new(-2i64, 3), Point2::new(1, -1));
+    assert_eq!((a.manhattan(&b), a.chebyshev(&b), a.distance_squared(&b)), (7, 4, 25));
+  }
+
+  #[test]
+  fn test_bounding_box() {
+    let b = BoundingBox::from_corners(&Point2::new(11u64, 1), &Point2::new(2, 5));
+    assert_eq!((b.min, b.max), (Point2::new(2, 1), Point2::new(11, 5)));
+    assert_eq!(b.area(), 50);
+    assert!(b.contains(&Point2::new(2, 5)) && !b.contains(&Point2::new(1, 3)));
+    let points = [Point3::new(1i32, 2, 3), Point3::new(-1, 4, 3), Point3::new(0, 0, 5)];
+    let b = BoundingBox::around(&points).unwrap();
+    assert_eq!(b.volume(), 3 * 5 * 3);
+    assert!(BoundingBox::<Point2<u8>>::around(&[]).is_none());
+  }
+
+  #[test]
+  fn test_kd_tree() {
+    // A pseudo random set of points with some duplicates
+    let mut seed = 17u64;
+    let points: Vec<Point3<i64>> = (0..300).map(|_| {
+      let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as i64 % 50 - 25
+      };
+      Point3::new(next(), next(), next())
+    }).collect();
+    let tree = KdTree::new(&points);
+    assert_eq!(tree.len(), 300);
+    for target in [Point3::new(0, 0, 0), points[7], Point3::new(40, -40, 3)] {
+      let mut expected: Vec<(i64, usize)> = points.iter().enumerate()
+        .map(|(i, p)| (target.distance_squared(p), i))
+        .collect();
+      expected.sort_unstable();
+
+      let nearest = tree.nearest(&target, 10);
+      assert_eq!(nearest.iter().map(|n| n.1).collect::<Vec<_>>(),
+                 expected[..10].iter().map(|e| e.0).collect::<Vec<_>>());
+      assert!(nearest.iter().all(|&(i, d)| target.distance_squared(tree.point(i)) == d));
+
+      let mut within: Vec<usize> = tree.within(&target, 100).iter().map(|w| w.0).collect();
+      within.sort_unstable();
+      let mut expected: Vec<usize> = expected.iter().filter(|e| e.0 <= 100).map(|e| e.1).collect();
+      expected.sort_unstable();
+      assert_eq!(within, expected);
+    }
+    assert!(KdTree::<Point2<u64>>::new(&[]).nearest(&Point2::new(1, 1), 3).is_empty());
+  }
+
+  #[test]
+  fn test_parse() {
+    let points: Vec<Point3<u64>> = parse_points(1, "1,2,3\n4, 5,6").unwrap();
+    assert_eq!(points, vec![Point3::new(1, 2, 3), Point3::new(4, 5, 6)]);
+    let e = parse_points::<Point2<u64>>(1, "1,2\n3,4,5").err().unwrap();
+    assert_eq!((e.line, e.column, e.message.as_str()), (2, 1, "Expected x,y"));
+    let e = parse_points::<Point2<u64>>(1, "1,x").err().unwrap();
+    assert_eq!((e.line, e.column, e.text.as_str()), (1, 3, "x"));
+  }
+}
@@ -0,0 +1,224 @@
+use crate::utils::{DayError, DayResult, Part};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fmt, io};
+
+/// The phases of a day that are timed.
+pub const PHASES: [&str; 3] = ["generator", "part1", "part2"];
+
+/// The recorded times of one run, keyed by day and then phase.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Run {
+  /// the git commit of the run or, outside of git, the timestamp
+  pub key: String,
+  /// the seconds since the epoch when the run was recorded
+  pub timestamp: u64,
+  pub days: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+/// The timing history of the runs, which is kept in history.yml next to the
+/// answers. Each key only has one run, which is updated by later runs.
+#[derive(Default, Deserialize, Serialize)]
+pub struct History {
+  pub runs: Vec<Run>,
+}
+
+/// What to compare the current run against.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Baseline {
+  /// the most recently recorded run
+  Previous,
+  /// the fastest recorded time of each phase
+  Best,
+}
+
+impl FromStr for Baseline {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "previous" => Ok(Baseline::Previous),
+      "best" => Ok(Baseline::Best),
+      _ => Err(format!("unknown baseline {s}, expected previous or best")),
+    }
+  }
+}
+
+impl fmt::Display for Baseline {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Baseline::Previous => write!(f, "the previous run"),
+      Baseline::Best => write!(f, "the best runs"),
+    }
+  }
+}
+
+/// A phase that took longer than the baseline.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Regression {
+  pub day: String,
+  pub phase: &'static str,
+  pub baseline: Duration,
+  pub current: Duration,
+}
+
+impl Regression {
+  /// How much slower the current run was as a percentage.
+  pub fn slowdown(&self) -> f64 {
+    (self.current.as_secs_f64() / self.baseline.as_secs_f64() - 1.0) * 100.0
+  }
+}
+
+impl fmt::Display for Regression {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:<8} {:<10} {:>12} -> {:>12} {}", self.day.replace("day", "Day "), self.phase,
+           format!("{:.2?}", self.baseline), format!("{:.2?}", self.current),
+           format!("(+{:.0}%)", self.slowdown()).red().bold())
+  }
+}
+
+/// Get the times of each phase that ran successfully.
+fn phase_times(result: &DayResult) -> BTreeMap<String, u64> {
+  let mut times = BTreeMap::new();
+  if matches!(result.error, Some(DayError::MissingInput(_) | DayError::Parse(_) |
+                                 DayError::TimedOut(_))) {
+    return times;
+  }
+  times.insert(PHASES[0].to_string(), result.generate_time.as_nanos() as u64);
+  for (part, phase) in Part::ALL.iter().zip(&PHASES[1..]) {
+    if let (time, Some(_)) = result.part(*part) {
+      times.insert(phase.to_string(), time.as_nanos() as u64);
+    }
+  }
+  times
+}
+
+/// The key for the current run, which is the git commit with a -dirty suffix
+/// for uncommitted changes, or the timestamp if git isn't available.
+pub fn current_key(timestamp: u64) -> String {
+  let git = |args: &[&str]| Command::new("git").args(args).output().ok()
+    .filter(|o| o.status.success())
+    .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+  match git(&["rev-parse", "--short", "HEAD"]) {
+    Some(commit) => match git(&["status", "--porcelain", "--untracked-files=no"]) {
+      Some(status) if !status.is_empty() => format!("{commit}-dirty"),
+      _ => commit,
+    },
+    None => format!("t{timestamp}"),
+  }
+}
+
+impl History {
+  fn make_filename(directory: &str) -> String {
+    Path::new(directory).join("history.yml").to_string_lossy().to_string()
+  }
+
+  pub fn read(directory: &str) -> Self {
+    if let Ok(f) = File::open(Self::make_filename(directory)) {
+      serde_yaml::from_reader(f).expect("Could not read history")
+    } else {
+      Self::default()
+    }
+  }
+
+  pub fn write(&self, directory: &str) -> io::Result<()> {
+    let f = File::create(Self::make_filename(directory))?;
+    serde_yaml::to_writer(f, self).map_err(io::Error::other)
+  }
+
+  /// Record the times from the results under the given key. The days that
+  /// weren't run keep their times from earlier runs with the same key.
+  pub fn record(&mut self, key: String, timestamp: u64, results: &[DayResult]) {
+    let mut run = match self.runs.iter().position(|r| r.key == key) {
+      Some(i) => self.runs.remove(i),
+      None => Run { key, ..Default::default() },
+    };
+    run.timestamp = timestamp;
+    for result in results {
+      let times = phase_times(result);
+      if !times.is_empty() {
+        run.days.insert(result.day.clone(), times);
+      }
+    }
+    self.runs.push(run);
+  }
+
+  /// Record the results under the key for the current commit and time.
+  pub fn record_now(&mut self, results: &[DayResult]) {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+      .map_or(0, |d| d.as_secs());
+    self.record(current_key(timestamp), timestamp, results);
+  }
+
+  /// Get the baseline time in nanoseconds for a day's phase.
+  fn baseline(&self, baseline: Baseline, day: &str, phase: &str) -> Option<u64> {
+    match baseline {
+      Baseline::Previous => self.runs.last()?.days.get(day)?.get(phase).copied(),
+      Baseline::Best => self.runs.iter()
+        .filter_map(|r| r.days.get(day)?.get(phase).copied())
+        .min(),
+    }
+  }
+
+  /// Find the phases of the results that are more than threshold percent
+  /// slower than the baseline.
+  pub fn compare(&self, baseline: Baseline, threshold: f64,
+                 results: &[DayResult]) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for result in results {
+      for (phase, current) in phase_times(result) {
+        let Some(base) = self.baseline(baseline, &result.day, &phase) else { continue };
+        if base > 0 && current as f64 > base as f64 * (1.0 + threshold / 100.0) {
+          regressions.push(Regression {
+            day: result.day.clone(),
+            phase: PHASES.iter().find(|p| **p == phase).expect("Unknown phase"),
+            baseline: Duration::from_nanos(base),
+            current: Duration::from_nanos(current),
+          });
+        }
+      }
+    }
+    regressions
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Baseline, History};
+  use crate::utils::DayResult;
+  use std::time::Duration;
+
+  fn result(day: &str, times: [u64; 3]) -> DayResult {
+    DayResult {
+      day: day.to_string(),
+      generate_time: Duration::from_micros(times[0]),
+      part1: (Duration::from_micros(times[1]), Some("1".to_string())),
+      part2: (Duration::from_micros(times[2]), Some("2".to_string())),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_compare() {
+    let mut history = History::default();
+    history.record("a".to_string(), 1, &[result("day1", [10, 100, 100])]);
+    history.record("b".to_string(), 2, &[result("day1", [20, 200, 100])]);
+    history.record("b".to_string(), 3, &[result("day2", [1, 1, 1])]);
+    assert_eq!(history.runs.len(), 2);
+    assert_eq!(history.runs[1].days.len(), 2);
+
+    let current = [result("day1", [21, 150, 150])];
+    let previous = history.compare(Baseline::Previous, 10.0, &current);
+    assert_eq!(previous.iter().map(|r| r.phase).collect::<Vec<_>>(), vec!["part2"]);
+    let best = history.compare(Baseline::Best, 10.0, &current);
+    assert_eq!(best.iter().map(|r| r.phase).collect::<Vec<_>>(),
+               vec!["generator", "part1", "part2"]);
+    assert_eq!(best[1].slowdown().round(), 50.0);
+  }
+}
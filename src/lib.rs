@@ -1,5 +1,15 @@
 extern crate core;
 
+pub mod alloc;
+pub mod examples;
+pub mod geometry;
+pub mod history;
+pub mod scaffold;
 pub mod utils;
+pub mod watch;
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static ALLOCATOR: alloc::Counting = alloc::Counting;
 
 day_list!(1, 2, 3, 4, 5, 6, 7, 8, 9);
\ No newline at end of file
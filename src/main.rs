@@ -1,12 +1,20 @@
-use aoc2025::{FUNCS,NAMES,utils};
+use aoc2025::{DAYS,day8,utils};
+use aoc2025::day8::Day8;
+use aoc2025::examples::Example;
+use aoc2025::history::{Baseline,History};
+use aoc2025::watch::{AnswerChange,Watcher};
 
 use argh::FromArgs;
 use colored::Colorize;
 use serde::{Deserialize,Serialize};
 use std::collections::BTreeMap;
-use std::fs;
-use std::fs::File;
-use std::path::Path;
+use std::fs::{self,File};
+use std::io::{self,Write};
+use std::path::{Path,PathBuf};
+use std::{thread,time};
+
+/// How often --watch checks for changes.
+const POLL_INTERVAL: time::Duration = time::Duration::from_millis(500);
 
 #[derive(FromArgs)]
 /** Solution for Advent of Code (https://adventofcode.com/)*/
@@ -15,9 +23,102 @@ struct Args {
     #[argh(option, short='i', default="String::from(\"input\")")]
     input: String,
 
-    /// a single day to execute (defaults to all)
+    /// the days to execute, such as 1,3,5-7 (defaults to all)
+    #[argh(positional)]
+    days: Option<utils::DaySelection>,
+
+    /// only solve the given part (1 or 2), skipping the other
+    #[argh(option, short='p')]
+    part: Option<utils::Part>,
+
+    /// run the examples for the selected days before the real inputs
+    #[argh(switch)]
+    examples: bool,
+
+    /// the directory with the examples and their answers.yml
+    #[argh(option, default="String::from(\"examples\")")]
+    examples_dir: String,
+
+    /// compare the times against the previous or best runs in history.yml
+    #[argh(option)]
+    compare: Option<Baseline>,
+
+    /// the percentage slowdown that --compare reports
+    #[argh(option, default="10.0")]
+    threshold: f64,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
+
+    /// read the input for a single day from this file, or stdin for -
+    #[argh(option, short='f')]
+    file: Option<String>,
+
+    /// compare the results against answers.yml instead of updating it
+    #[argh(switch)]
+    check: bool,
+
+    /// the output format for the results: text, json, or csv
+    #[argh(option, default="utils::Format::Text")]
+    format: utils::Format,
+
+    /// the number of days to run in parallel (0 for one per core)
+    #[argh(option, short='j', default="1")]
+    jobs: usize,
+
+    /// the number of timed runs of each day to summarize
+    #[argh(option, default="1")]
+    repeat: usize,
+
+    /// the number of untimed runs of each day before the timed ones
+    #[argh(option, default="0")]
+    warmup: usize,
+
+    /// the seconds that each day may run before it's reported as timed out
+    #[argh(option)]
+    timeout: Option<f64>,
+
+    /// write the day 8 circuits as a DOT graph to this file
+    #[argh(option)]
+    visualize: Option<String>,
+
+    /// the number of connections that --visualize makes (defaults to 1000)
+    #[argh(option)]
+    connections: Option<usize>,
+
+    /// re-run a single day whenever its input, or examples with --examples,
+    /// change
+    #[argh(switch)]
+    watch: bool,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Report(ReportArgs),
+    New(NewArgs),
+}
+
+#[derive(FromArgs)]
+/// Write a markdown table of the answers and times.
+#[argh(subcommand, name = "report")]
+struct ReportArgs {
+    /// the file to write the report to (defaults to stdout)
+    #[argh(option, short='o')]
+    output: Option<String>,
+
+    /// hide the answers in the report
+    #[argh(switch)]
+    redact: bool,
+}
+
+#[derive(FromArgs)]
+/// Create and register the module for a new day with an empty input.
+#[argh(subcommand, name = "new")]
+struct NewArgs {
+    /// the number of the day
     #[argh(positional)]
-    day: Option<usize>,
+    day: usize,
 }
 
 #[derive(Default,Deserialize,Serialize)]
@@ -39,17 +140,59 @@ impl Answers {
         }
     }
 
-    fn update(&mut self, delta_list: &Vec<utils::DayResult>) {
+    fn update(&mut self, delta_list: &Vec<utils::DayResult>,
+              log: &mut dyn Write) -> io::Result<()> {
+        // Days that failed keep their previous answers, as do skipped parts
         for delta in delta_list {
-            let new_val = delta.get_answers();
+            if delta.error.is_some() {
+                continue;
+            }
+            let mut new_val = self.days.get(&delta.day).cloned().unwrap_or_default();
+            new_val.resize(utils::Part::ALL.len(), String::new());
+            for (i, part) in utils::Part::ALL.iter().enumerate() {
+                if let Some(answer) = &delta.part(*part).1 {
+                    new_val[i] = answer.clone();
+                }
+            }
             if let Some(prev) =
               self.days.insert(delta.day.to_string(), new_val.clone()) {
                 if prev != new_val {
-                    println!("{}", format!("Output for {} changed from {:?} to {:?}!",
-                                           delta.pretty_day(), prev, new_val).bold());
+                    writeln!(log, "{}", format!("Output for {} changed from {:?} to {:?}!",
+                                                delta.pretty_day(), prev, new_val).bold())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compare the results against the recorded answers, printing a table with
+    /// the status of each part. Returns true if every part matched.
+    fn check(&self, results: &Vec<utils::DayResult>,
+             log: &mut dyn Write) -> io::Result<bool> {
+        let mut passed = true;
+        writeln!(log, "\n{:<8} {:<6} {:>20} {:>20}  {}", "Day".bold(), "Part".bold(),
+                 "Expected".bold(), "Actual".bold(), "Status".bold())?;
+        for r in results {
+            let expected = self.days.get(&r.day);
+            for (i, part) in utils::Part::ALL.iter().enumerate() {
+                if r.is_skipped(*part) {
+                    continue;
                 }
+                let expected = expected.and_then(|e| e.get(i)).filter(|e| !e.is_empty());
+                let actual = r.part(*part).1.as_ref();
+                let status = match (expected, actual) {
+                    (_, None) => "ERROR".red().bold(),
+                    (Some(e), Some(a)) if e == a => "pass".green(),
+                    (Some(_), _) => "FAIL".red().bold(),
+                    (None, _) => "MISSING".yellow().bold(),
+                };
+                passed &= actual.is_some() && expected == actual;
+                writeln!(log, "{:<8} {:<6} {:>20} {:>20}  {}", r.pretty_day(), part,
+                         expected.map_or("-", |e| e.as_str()),
+                         actual.map_or("-", |a| a.as_str()), status)?;
             }
         }
+        Ok(passed)
     }
 
     fn write(&self, directory: &str) {
@@ -63,42 +206,260 @@ impl Answers {
     }
 }
 
+/// Re-run the day whenever its input, or its examples with --examples, change
+/// and show its answers next to those from the previous run.
+fn watch(args: &Args, day: &'static dyn utils::DaySolution,
+         run_day: &dyn Fn(&'static dyn utils::DaySolution, &str) -> utils::DayResult,
+         parts: &[utils::Part]) -> ! {
+    let input_path = args.file.clone()
+        .unwrap_or_else(|| utils::input_path(&args.input, &day.name()));
+    let mut paths = vec![PathBuf::from(&input_path)];
+    if args.examples {
+        let dir = Path::new(&args.examples_dir);
+        paths.push(dir.join("answers.yml"));
+        if let Ok(entries) = fs::read_dir(dir.join(day.name())) {
+            paths.extend(entries.filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|e| e == "txt")));
+        }
+    }
+    let mut watcher = Watcher::new(paths);
+    let watched: Vec<String> = watcher.paths().iter().map(|p| p.display().to_string()).collect();
+    let mut previous = None;
+    loop {
+        if watcher.changed() {
+            if args.examples {
+                match Example::load(&args.examples_dir, &[day]) {
+                    Ok(examples) => for example in examples {
+                        println!("{}", example.run(parts));
+                    },
+                    Err(e) => println!("Can't read examples from {}: {e}", args.examples_dir),
+                }
+                println!();
+            }
+            let result = match utils::read_input(&input_path) {
+                Ok(input) => run_day(day, &input),
+                Err(_) => utils::DayResult::missing_input(day.name(), input_path.clone()),
+            };
+            println!("{result}");
+            for change in AnswerChange::compare(previous.as_ref(), &result, parts) {
+                println!("{change}");
+            }
+            println!("\n{} {}\n", "Watching".dimmed(), watched.join(", ").dimmed());
+            previous = Some(result);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
 fn main() {
     let args: Args = argh::from_env();
+    // Scaffolding a day happens before it can be compiled in, so nothing runs
+    if let Some(Command::New(new)) = &args.command {
+        match aoc2025::scaffold::create(".", &args.input, new.day) {
+            Ok(paths) => {
+                for path in paths {
+                    println!("{} {path}", "Wrote".bold());
+                }
+            }
+            Err(e) => {
+                eprintln!("Can't add day{}: {e}", new.day);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    // Keep stdout clean for the structured formats and reports
+    let mut log: Box<dyn Write> = match (&args.format, &args.command) {
+        (_, Some(Command::Report(ReportArgs { output: None, .. }))) => Box::new(io::stderr()),
+        (utils::Format::Text, _) => Box::new(io::stdout()),
+        _ => Box::new(io::stderr()),
+    };
     // Read the inputs from the given directory
-    println!("{} {}\n", "Reading from".bold(), &args.input);
-
-    // Did the user pick a single day to run
-    let (day_filter, inputs) = match args.day {
-        Some(day) => {
-            let name = format!("day{}", day);
-            let f = Some(NAMES.iter().position(|x| **x == name)
-              .expect("Requested an unimplemented day"));
-            let mut inputs = vec!["".to_owned(); NAMES.len()];
-            inputs[day - 1] = fs::read_to_string(Path::new(&format!("{}/{name}.txt", args.input)))
-                .expect("Can't read input dir for day{d}");
-            (f, inputs)
+    let source = args.file.as_deref().map_or(args.input.as_str(), |f| match f {
+        "-" => "stdin",
+        _ => f,
+    });
+    writeln!(log, "{} {}\n", "Reading from".bold(), source).unwrap();
+
+    // Did the user pick the days to run
+    let days: Vec<_> = match &args.days {
+        Some(selection) => {
+            let unknown: Vec<String> = selection.days().iter()
+                .filter(|&&day| DAYS.by_day(day).is_none())
+                .map(|day| day.to_string())
+                .collect();
+            if !unknown.is_empty() {
+                eprintln!("Requested unimplemented days {}", unknown.join(", "));
+                std::process::exit(2);
+            }
+            selection.days().iter().filter_map(|&day| DAYS.by_day(day)).collect()
+        }
+        None => DAYS.iter().collect(),
+    };
+    let parts = args.part.map_or(utils::Part::ALL.to_vec(), |p| vec![p]);
+    let options = utils::RunOptions {
+        warmup: args.warmup,
+        repeat: args.repeat,
+        parts: parts.clone(),
+    };
+    let timeout = args.timeout.map(time::Duration::from_secs_f64);
+    let run_day = |d: &'static dyn utils::DaySolution, input: &str| match timeout {
+        Some(limit) => utils::run_with_timeout(d, input, &options, limit),
+        None => d.run_with(input, &options),
+    };
+
+    if args.connections.is_some() && args.visualize.is_none() {
+        eprintln!("--connections is only used by --visualize");
+        std::process::exit(2);
+    }
+    if let Some(path) = &args.visualize {
+        let other_days = args.days.as_ref().is_some_and(|s| s.days() != [8]);
+        if other_days || args.part.is_some() || args.check || args.examples || args.watch
+            || args.compare.is_some() || args.format != utils::Format::Text || args.jobs != 1
+            || args.repeat != 1 || args.warmup != 0 || args.timeout.is_some()
+            || args.command.is_some() {
+            eprintln!("--visualize only draws day 8, and can't be used with other days, options \
+                       that run days such as --part or --jobs, or a subcommand");
+            std::process::exit(2);
+        }
+        // The day 8 input from --file or the input directory
+        let input_path = args.file.clone()
+            .unwrap_or_else(|| utils::input_path(&args.input, "day8"));
+        let connections = args.connections
+            .unwrap_or(day8::CircuitScore::default().connections);
+        let dot = utils::read_input(&input_path)
+            .map_err(|e| format!("Can't read {input_path}: {e}"))
+            .and_then(|input| Day8::visualize(&input, connections)
+                .map_err(|e| format!("Can't visualize day 8: {e}")))
+            .and_then(|dot| fs::write(path, dot).map_err(|e| format!("Can't write {path}: {e}")));
+        if let Err(e) = dot {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        writeln!(log, "{} {path}", "Wrote".bold()).unwrap();
+        return;
+    }
+
+    if args.file.is_some() && (days.len() != 1 || args.check) {
+        eprintln!("--file needs exactly one day and can't be used with --check");
+        std::process::exit(2);
+    }
+
+    if args.watch {
+        if days.len() != 1 || args.check || args.command.is_some()
+            || args.file.as_deref() == Some("-") {
+            eprintln!("--watch needs exactly one day and a file input, and can't be used with --check");
+            std::process::exit(2);
+        }
+        watch(&args, days[0], &run_day, &parts);
+    }
+
+    if args.examples {
+        let examples = Example::load(&args.examples_dir, &days)
+            .unwrap_or_else(|e| panic!("Can't read examples from {}: {e}", args.examples_dir));
+        let runs = utils::run_parallel(args.jobs, &examples, &|e| e.run(&parts));
+        for run in &runs {
+            writeln!(log, "{run}").unwrap();
+        }
+        writeln!(log).unwrap();
+        if !runs.iter().all(|r| r.passed()) {
+            writeln!(log, "{}", "Some examples failed".red().bold()).unwrap();
+            std::process::exit(1);
+        }
+    }
+
+    let inputs = match &args.file {
+        // An ad-hoc input for a single day
+        Some(file) => match utils::read_input(file) {
+            Ok(input) => vec![Ok(input)],
+            Err(e) => {
+                eprintln!("Can't read {file}: {e}");
+                std::process::exit(1);
+            }
         },
         None => {
-            let inputs = utils::read_inputs(&args.input, NAMES)
-                .expect("Can't read input dir");
-            (None, inputs)
+            let names: Vec<String> = days.iter().map(|d| d.name()).collect();
+            utils::read_inputs(&args.input, &names)
         }
     };
 
+    let work: Vec<_> = days.into_iter().zip(&inputs).collect();
     let (elapsed, results) = utils::time(&|| {
-        FUNCS.iter().enumerate()
-          .filter(|(p, _)| day_filter.is_none_or(|d| &d == p))
-          .map(|(p, f)| f(&inputs[p]))
-          .collect::<Vec<utils::DayResult>>()
+        utils::run_parallel(args.jobs, &work, &|(d, input)| match input {
+            Ok(input) => run_day(*d, input),
+            Err(_) => utils::DayResult::missing_input(
+                d.name(), utils::input_path(&args.input, &d.name())),
+        })
     });
 
-    for r in &results {
-        println!("{}", r);
+    if let Some(Command::Report(report)) = &args.command {
+        let mut out: Box<dyn Write> = match &report.output {
+            Some(path) => Box::new(File::create(path)
+                .unwrap_or_else(|e| panic!("Can't create {path}: {e}"))),
+            None => Box::new(io::stdout()),
+        };
+        utils::write_markdown(&mut out, &results, elapsed, report.redact)
+            .expect("Can't write report");
+        return;
+    }
+
+    match args.format {
+        utils::Format::Text => {
+            for r in &results {
+                println!("{}", r);
+            }
+            println!("{} {}", "Overall runtime".bold(), format!("({:.2?})", elapsed).dimmed());
+            println!("{} {}", "Sum of day wall times".bold(),
+                     format!("({:.2?})", utils::day_wall_sum(&results)).dimmed());
+            if let Some(cpu) = utils::day_cpu_sum(&results) {
+                println!("{} {}", "Sum of day CPU times".bold(), format!("({cpu:.2?})").dimmed());
+            }
+            let missing: Vec<&str> = results.iter()
+                .filter_map(|r| match &r.error {
+                    Some(utils::DayError::MissingInput(path)) => Some(path.as_str()),
+                    _ => None,
+                }).collect();
+            if !missing.is_empty() {
+                println!("{} {}", "Missing inputs".yellow().bold(), missing.join(", "));
+            }
+        }
+        utils::Format::Json => utils::RunRecord::new(&results, elapsed)
+            .write_json(&mut io::stdout()).unwrap(),
+        utils::Format::Csv => utils::RunRecord::new(&results, elapsed)
+            .write_csv(&mut io::stdout()).unwrap(),
+    }
+
+    // The answers only record the real inputs
+    if args.file.is_some() {
+        return;
+    }
+    let mut history = History::read(&args.input);
+    if let Some(baseline) = args.compare {
+        let regressions = history.compare(baseline, args.threshold, &results);
+        if regressions.is_empty() {
+            writeln!(log, "\n{} {} against {baseline}",
+                     "No phases slowed down by more than".bold(),
+                     format!("{}%", args.threshold).bold()).unwrap();
+        } else {
+            writeln!(log, "\n{} {} against {baseline}",
+                     "Phases that slowed down by more than".bold(),
+                     format!("{}%", args.threshold).bold()).unwrap();
+            for r in regressions {
+                writeln!(log, "{r}").unwrap();
+            }
+        }
     }
-    println!("{} {}", "Overall runtime".bold(), format!("({:.2?})", elapsed).dimmed());
 
     let mut old_answers = Answers::read(&args.input);
-    old_answers.update(&results);
+    if args.check {
+        if !old_answers.check(&results, &mut log).unwrap() {
+            writeln!(log, "\n{}", "Some answers did not match answers.yml".red().bold()).unwrap();
+            std::process::exit(1);
+        }
+        return;
+    }
+    old_answers.update(&results, &mut log).unwrap();
     old_answers.write(&args.input);
+    history.record_now(&results);
+    history.write(&args.input).expect("Couldn't write history");
 }
\ No newline at end of file
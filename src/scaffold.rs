@@ -0,0 +1,128 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The skeleton of a new day's module, with {N} standing in for its number.
+const TEMPLATE: &str = r#"use crate::utils::{ParseError, Solution, SolveError};
+
+pub struct Day{N};
+
+impl Solution for Day{N} {
+    const DAY: usize = {N};
+    const TITLE: &'static str = "Day {N}";
+    type Input = Vec<String>;
+    type Output1 = u64;
+    type Output2 = u64;
+
+    fn part1(_input: &Vec<String>) -> Result<u64, SolveError> {
+        Err(SolveError::new("Part 1 isn't solved yet"))
+    }
+
+    fn part2(_input: &Vec<String>) -> Result<u64, SolveError> {
+        Err(SolveError::new("Part 2 isn't solved yet"))
+    }
+
+    fn generator(input: &str) -> Result<Vec<String>, ParseError> {
+        Ok(input.lines().map(String::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Day{N};
+    use crate::utils::Solution;
+
+    const INPUT: &str = "";
+
+    #[test]
+    fn test_generator() {
+        Day{N}::generator(INPUT).unwrap();
+    }
+
+    #[test]
+    #[ignore = "the example hasn't been filled in"]
+    fn test_part_1() {
+        let input = Day{N}::generator(INPUT).unwrap();
+        assert_eq!(Day{N}::part1(&input).unwrap(), 0);
+    }
+
+    #[test]
+    #[ignore = "the example hasn't been filled in"]
+    fn test_part_2() {
+        let input = Day{N}::generator(INPUT).unwrap();
+        assert_eq!(Day{N}::part2(&input).unwrap(), 0);
+    }
+}
+"#;
+
+/// The source of the module for the given day.
+pub fn module_source(day: usize) -> String {
+  TEMPLATE.replace("{N}", &day.to_string())
+}
+
+/// Add the day to the day_list! in the source of lib.rs, keeping the days in
+/// order.
+pub fn register(lib: &str, day: usize) -> Result<String, String> {
+  const MACRO: &str = "day_list!(";
+  let start = lib.find(MACRO).ok_or("Can't find day_list! in lib.rs")? + MACRO.len();
+  let end = start + lib[start..].find(')').ok_or("Unterminated day_list! in lib.rs")?;
+  let mut days = lib[start..end].split(',')
+    .map(|d| d.trim().parse::<usize>().map_err(|e| format!("Bad day {d:?} in day_list!: {e}")))
+    .collect::<Result<Vec<_>, _>>()?;
+  if days.contains(&day) {
+    return Err(format!("day{day} is already registered"));
+  }
+  days.push(day);
+  days.sort_unstable();
+  let days: Vec<String> = days.iter().map(|d| d.to_string()).collect();
+  Ok(format!("{}{}{}", &lib[..start], days.join(", "), &lib[end..]))
+}
+
+/// Create src/dayN.rs in the crate directory, register it in src/lib.rs and
+/// create an empty input for it. Existing modules and inputs aren't
+/// overwritten. The paths that were written are returned.
+pub fn create(crate_dir: &str, input_dir: &str, day: usize) -> io::Result<Vec<String>> {
+  if !(1..=25).contains(&day) {
+    return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("day {day} isn't 1 to 25")));
+  }
+  let module = Path::new(crate_dir).join("src").join(format!("day{day}.rs"));
+  let lib = Path::new(crate_dir).join("src").join("lib.rs");
+  let input = Path::new(input_dir).join(format!("day{day}.txt"));
+  if module.exists() {
+    return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                              format!("{} already exists", module.display())));
+  }
+  let registered = register(&fs::read_to_string(&lib)?, day)
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+  fs::write(&module, module_source(day))?;
+  fs::write(&lib, registered)?;
+  let mut created = vec![module.display().to_string(), lib.display().to_string()];
+  if !input.exists() {
+    fs::create_dir_all(input_dir)?;
+    fs::write(&input, "")?;
+    created.push(input.display().to_string());
+  }
+  Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{module_source, register};
+
+  #[test]
+  fn test_register() {
+    let lib = "pub mod utils;\n\nday_list!(1, 2, 4);\n";
+    assert_eq!(register(lib, 3).unwrap(), "pub mod utils;\n\nday_list!(1, 2, 3, 4);\n");
+    assert_eq!(register(lib, 10).unwrap(), "pub mod utils;\n\nday_list!(1, 2, 4, 10);\n");
+    assert!(register(lib, 2).is_err());
+    assert!(register("pub mod utils;", 2).is_err());
+  }
+
+  #[test]
+  fn test_module_source() {
+    let source = module_source(12);
+    assert!(source.contains("pub struct Day12;"));
+    assert!(source.contains("const DAY: usize = 12;"));
+    assert!(!source.contains("{N}"));
+  }
+}
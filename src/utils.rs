@@ -1,17 +1,29 @@
+use crate::alloc::{self, AllocStats, DayAllocs};
 use colored::Colorize;
+use serde::Serialize;
+use std::any::Any;
+use std::borrow::Cow;
 use std::cmp::min;
+use std::collections::BTreeMap;
+use std::ops::{Index, IndexMut};
 use std::{fmt, fs, io};
 use std::path::Path;
-use std::time;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::{thread, time};
 
 /// Format the output of each line of the output.
-/// Includes the category, time, and result.
+/// Includes the category, time, memory when it was counted, and result.
 fn pretty_print(f: &mut fmt::Formatter<'_>, line: &str,
-                duration: time::Duration,
+                duration: time::Duration, allocs: Option<AllocStats>,
                 output: Option<&str>) -> fmt::Result {
   const DISPLAY_WIDTH: usize = 40;
 
-  let duration = format!("({:.2?})", duration);
+  let duration = match allocs {
+    Some(allocs) => format!("({:.2?}, {})", duration, allocs),
+    None => format!("({:.2?})", duration),
+  };
   write!(f, "{} {}", line, duration.dimmed())?;
 
   match output {
@@ -35,6 +47,20 @@ fn pretty_print(f: &mut fmt::Formatter<'_>, line: &str,
   }
 }
 
+/// The CPU time used by the current thread so far, or None where it can't be
+/// measured.
+pub fn thread_cpu_time() -> Option<time::Duration> {
+  #[cfg(unix)]
+  {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: ts is a valid timespec for the clock to be written into
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) } == 0 {
+      return Some(time::Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32));
+    }
+  }
+  None
+}
+
 /// Time the given function, returning its result and the elapsed time
 pub fn time<T>(func: &dyn Fn() -> T) -> (time::Duration, T) {
   let start = time::Instant::now();
@@ -43,76 +69,1367 @@ pub fn time<T>(func: &dyn Fn() -> T) -> (time::Duration, T) {
   (start.elapsed(), result)
 }
 
-/// Read the data files from the in_dir into a vector of string.
-pub fn read_inputs(in_dir: &str, days: &[&str]) -> io::Result<Vec<String>> {
-  let data: Vec<io::Result<String>> = days.iter()
-    .map(|&day| {
-      let filename = format!("{in_dir}/{day}.txt");
-      fs::read_to_string(Path::new(&filename))
-    })
-    .collect();
-  data.into_iter().collect()
+/// Apply func to each item using up to jobs threads. The results are in the
+/// same order as the items regardless of which thread ran them. A jobs of 0
+/// uses one thread per available core.
+pub fn run_parallel<T: Sync, R: Send>(jobs: usize, items: &[T],
+                                      func: &(dyn Fn(&T) -> R + Sync)) -> Vec<R> {
+  let jobs = match jobs {
+    0 => thread::available_parallelism().map_or(1, |n| n.get()),
+    n => n,
+  }.min(items.len());
+  if jobs <= 1 {
+    return items.iter().map(func).collect();
+  }
+  let next = AtomicUsize::new(0);
+  let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+    let workers: Vec<_> = (0..jobs).map(|_| scope.spawn(|| {
+      let mut done = Vec::new();
+      loop {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        match items.get(i) {
+          Some(item) => done.push((i, func(item))),
+          None => return done,
+        }
+      }
+    })).collect();
+    workers.into_iter()
+      .flat_map(|w| w.join().expect("Worker thread panicked"))
+      .collect()
+  });
+  results.sort_unstable_by_key(|(i, _)| *i);
+  results.into_iter().map(|(_, r)| r).collect()
+}
+
+/// Read a single input file, where "-" reads from stdin.
+pub fn read_input(path: &str) -> io::Result<String> {
+  match path {
+    "-" => io::read_to_string(io::stdin()),
+    _ => fs::read_to_string(Path::new(path)),
+  }
+}
+
+/// The path of the input file for the given day name.
+pub fn input_path(in_dir: &str, day: &str) -> String {
+  format!("{in_dir}/{day}.txt")
+}
+
+/// Read the data files from the in_dir into a vector of string. Each day's
+/// input is read separately, so that a missing file only affects its day.
+pub fn read_inputs<S: AsRef<str>>(in_dir: &str, days: &[S]) -> Vec<io::Result<String>> {
+  days.iter()
+    .map(|day| read_input(&input_path(in_dir, day.as_ref())))
+    .collect()
+}
+
+/// An error in a day's input. The line and column are 1 based and text is
+/// the part of the input that couldn't be parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+  pub day: usize,
+  pub line: usize,
+  pub column: usize,
+  pub text: String,
+  pub message: String,
+}
+
+impl ParseError {
+  /// Create an error for the given token, which must be a slice of the input,
+  /// so that the line and column can be computed from its position.
+  pub fn new(day: usize, input: &str, token: &str, message: impl Into<String>) -> Self {
+    let offset = (token.as_ptr() as usize).wrapping_sub(input.as_ptr() as usize);
+    debug_assert!(offset <= input.len(), "token is not part of the input");
+    let before = &input[..offset.min(input.len())];
+    let line_start = before.rfind('\n').map_or(0, |p| p + 1);
+    ParseError {
+      day,
+      line: before.matches('\n').count() + 1,
+      column: before[line_start..].chars().count() + 1,
+      text: token.to_string(),
+      message: message.into(),
+    }
+  }
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "day{} line {} column {}: {} at {:?}",
+           self.day, self.line, self.column, self.message, self.text)
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a token from the input, reporting where it was on failure.
+pub fn parse_token<T>(day: usize, input: &str, token: &str) -> Result<T, ParseError>
+  where T: FromStr, T::Err: fmt::Display {
+  token.parse().map_err(|e: T::Err| ParseError::new(day, input, token, e.to_string()))
+}
+
+/// An error from a part that couldn't be solved from a parsed input.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SolveError {
+  pub message: String,
+}
+
+impl SolveError {
+  pub fn new(message: impl Into<String>) -> Self {
+    SolveError { message: message.into() }
+  }
 }
 
-/// The times and results of running a day's code.
+impl fmt::Display for SolveError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
+
+impl std::error::Error for SolveError {}
+
+/// The reasons that a day failed to run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DayError {
+  /// The input file with the given path couldn't be read.
+  MissingInput(String),
+  Parse(ParseError),
+  Solve(Part, SolveError),
+  /// The parameters given to the day couldn't be applied.
+  Params(SolveError),
+  /// The day didn't finish within the given time.
+  TimedOut(time::Duration),
+}
+
+impl fmt::Display for DayError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DayError::MissingInput(path) => write!(f, "missing input {path}"),
+      DayError::Parse(e) => write!(f, "{e}"),
+      DayError::Solve(part, e) => write!(f, "part {part}: {e}"),
+      DayError::Params(e) => write!(f, "parameters: {e}"),
+      DayError::TimedOut(limit) => write!(f, "timed out after {limit:.2?}"),
+    }
+  }
+}
+
+impl std::error::Error for DayError {}
+
+/// Summary statistics over the repeated timings of a phase.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+  pub runs: usize,
+  pub min: time::Duration,
+  pub median: time::Duration,
+  pub mean: time::Duration,
+  pub std_dev: time::Duration,
+}
+
+impl Stats {
+  pub fn new(samples: &[time::Duration]) -> Self {
+    if samples.is_empty() {
+      return Stats::default();
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let n = sorted.len();
+    let median = if n.is_multiple_of(2) {
+      (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+    } else {
+      sorted[n / 2]
+    };
+    let mean = sorted.iter().map(|d| d.as_secs_f64()).sum::<f64>() / n as f64;
+    let variance = if n > 1 {
+      sorted.iter().map(|d| (d.as_secs_f64() - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+    } else {
+      0.0
+    };
+    Stats {
+      runs: n,
+      min: sorted[0],
+      median,
+      mean: time::Duration::from_secs_f64(mean),
+      std_dev: time::Duration::from_secs_f64(variance.sqrt()),
+    }
+  }
+}
+
+impl fmt::Display for Stats {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "min {:.2?} median {:.2?} mean {:.2?} σ {:.2?} over {} runs",
+           self.min, self.median, self.mean, self.std_dev, self.runs)
+  }
+}
+
+/// The statistics for each phase of a day that was run repeatedly.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DayStats {
+  pub generate: Stats,
+  pub part1: Stats,
+  pub part2: Stats,
+}
+
+/// The times and results of running a day's code. If the day failed, the
+/// error is set and the parts that weren't solved have no answer. When the
+/// day is run repeatedly, the times are the medians and stats has the details.
+#[derive(Default)]
 pub struct DayResult {
   pub day: String,
   pub generate_time: time::Duration,
-  pub part1: (time::Duration, String),
-  pub part2: (time::Duration, String),
+  pub part1: (time::Duration, Option<String>),
+  pub part2: (time::Duration, Option<String>),
+  pub error: Option<DayError>,
+  pub stats: Option<DayStats>,
+  /// the memory used by each phase when the allocations are counted
+  pub allocs: Option<DayAllocs>,
+  /// the CPU time of the thread that ran the day, which unlike the times
+  /// above doesn't include waiting for a core
+  pub cpu_time: Option<time::Duration>,
 }
 
 impl DayResult {
+  /// The result for a day whose input file couldn't be read.
+  pub fn missing_input(day: String, path: String) -> Self {
+    DayResult { day, error: Some(DayError::MissingInput(path)), ..Default::default() }
+  }
+
+  /// The result for a day that didn't finish within the limit.
+  pub fn timed_out(day: String, limit: time::Duration) -> Self {
+    DayResult { day, error: Some(DayError::TimedOut(limit)), ..Default::default() }
+  }
+
   /// Return the pretty name for the day
   pub fn pretty_day(&self) -> String {
     self.day.replace("day", "Day ")
   }
 
-  /// Get the answers without the times
-  pub fn get_answers(&self) -> Vec<String> {
-    vec![self.part1.1.to_string(), self.part2.1.to_string()]
+  /// The time spent on the generator and both parts
+  pub fn total_time(&self) -> time::Duration {
+    self.generate_time + self.part1.0 + self.part2.0
+  }
+
+  /// Was the given part skipped rather than run?
+  pub fn is_skipped(&self, part: Part) -> bool {
+    self.error.is_none() && self.part(part).1.is_none()
+  }
+
+  /// Get the result of the given part
+  pub fn part(&self, part: Part) -> &(time::Duration, Option<String>) {
+    match part {
+      Part::One => &self.part1,
+      Part::Two => &self.part2,
+    }
+  }
+
+  fn part_mut(&mut self, part: Part) -> &mut (time::Duration, Option<String>) {
+    match part {
+      Part::One => &mut self.part1,
+      Part::Two => &mut self.part2,
+    }
+  }
+
+  /// Combine the results of running a day several times into one result
+  /// with the median times and the statistics for each phase.
+  pub fn summarize(mut runs: Vec<DayResult>) -> DayResult {
+    if runs.len() <= 1 {
+      return runs.pop().unwrap_or_default();
+    }
+    let stats = |phase: &dyn Fn(&DayResult) -> time::Duration| {
+      Stats::new(&runs.iter().map(phase).collect::<Vec<_>>())
+    };
+    let stats = DayStats {
+      generate: stats(&|r| r.generate_time),
+      part1: stats(&|r| r.part1.0),
+      part2: stats(&|r| r.part2.0),
+    };
+    let cpu_times: Option<Vec<_>> = runs.iter().map(|r| r.cpu_time).collect();
+    let mut result = runs.pop().unwrap();
+    result.cpu_time = cpu_times.map(|t| Stats::new(&t).median);
+    result.generate_time = stats.generate.median;
+    result.part1.0 = stats.part1.median;
+    result.part2.0 = stats.part2.median;
+    result.stats = Some(stats);
+    result
   }
 }
 
 impl fmt::Display for DayResult {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let duration = format!("({:.2?})", self.generate_time + self.part1.0 + self.part2.0);
+    let duration = format!("({:.2?})", self.total_time());
     writeln!(f, "{} {}", self.pretty_day().bold(), duration.dimmed())?;
-    pretty_print(f," · Generator", self.generate_time, None)?;
-    pretty_print(f, " · Part 1", self.part1.0, Some(&self.part1.1))?;
-    pretty_print(f, " · Part 2", self.part2.0, Some(&self.part2.1))
+    match &self.error {
+      Some(e @ DayError::MissingInput(_)) =>
+        return writeln!(f, " · {} {}", "Skipped".yellow().bold(), e),
+      Some(e @ DayError::TimedOut(_)) =>
+        return writeln!(f, " · {} {}", "Failed".red().bold(), e),
+      _ => {}
+    }
+    pretty_print(f," · Generator", self.generate_time, self.allocs.map(|a| a.generate), None)?;
+    if let Some(stats) = &self.stats {
+      writeln!(f, "     {}", stats.generate.to_string().dimmed())?;
+    }
+    for part in Part::ALL {
+      if self.is_skipped(part) {
+        writeln!(f, " · Part {part} {}", "(skipped)".dimmed())?;
+      }
+      if let (time, Some(answer)) = self.part(part) {
+        pretty_print(f, &format!(" · Part {part}"), *time, self.allocs.map(|a| a.part(part)),
+                     Some(answer))?;
+        if let Some(stats) = &self.stats {
+          let stats = match part {
+            Part::One => stats.part1,
+            Part::Two => stats.part2,
+          };
+          writeln!(f, "     {}", stats.to_string().dimmed())?;
+        }
+      }
+    }
+    match &self.error {
+      Some(e) => writeln!(f, " · {} {}", "Failed".red().bold(), e),
+      None => Ok(()),
+    }
+  }
+}
+
+/// The output formats for the results of a run.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Format {
+  /// Colored text for the terminal.
+  #[default]
+  Text,
+  /// A single JSON document.
+  Json,
+  /// One CSV line per day, followed by the overall runtime.
+  Csv,
+}
+
+impl FromStr for Format {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "text" => Ok(Format::Text),
+      "json" => Ok(Format::Json),
+      "csv" => Ok(Format::Csv),
+      _ => Err(format!("unknown format {s}, expected one of json, csv or text")),
+    }
+  }
+}
+
+/// The machine readable form of a DayResult with the times in nanoseconds.
+#[derive(Debug, Serialize)]
+pub struct DayRecord {
+  pub day: String,
+  pub generate_ns: u64,
+  pub part1_ns: u64,
+  pub part1: Option<String>,
+  pub part2_ns: u64,
+  pub part2: Option<String>,
+  pub total_ns: u64,
+  pub error: Option<String>,
+  pub stats: Option<DayStatsRecord>,
+  pub allocs: Option<DayAllocs>,
+  pub cpu_ns: Option<u64>,
+}
+
+/// The machine readable form of Stats with the times in nanoseconds.
+#[derive(Debug, Serialize)]
+pub struct StatsRecord {
+  pub runs: usize,
+  pub min_ns: u64,
+  pub median_ns: u64,
+  pub mean_ns: u64,
+  pub std_dev_ns: u64,
+}
+
+impl From<&Stats> for StatsRecord {
+  fn from(s: &Stats) -> Self {
+    StatsRecord {
+      runs: s.runs,
+      min_ns: s.min.as_nanos() as u64,
+      median_ns: s.median.as_nanos() as u64,
+      mean_ns: s.mean.as_nanos() as u64,
+      std_dev_ns: s.std_dev.as_nanos() as u64,
+    }
+  }
+}
+
+/// The machine readable form of DayStats.
+#[derive(Debug, Serialize)]
+pub struct DayStatsRecord {
+  pub generate: StatsRecord,
+  pub part1: StatsRecord,
+  pub part2: StatsRecord,
+}
+
+impl DayStatsRecord {
+  fn phases(&self) -> [&StatsRecord; 3] {
+    [&self.generate, &self.part1, &self.part2]
+  }
+}
+
+impl From<&DayResult> for DayRecord {
+  fn from(r: &DayResult) -> Self {
+    DayRecord {
+      day: r.day.clone(),
+      generate_ns: r.generate_time.as_nanos() as u64,
+      part1_ns: r.part1.0.as_nanos() as u64,
+      part1: r.part1.1.clone(),
+      part2_ns: r.part2.0.as_nanos() as u64,
+      part2: r.part2.1.clone(),
+      total_ns: r.total_time().as_nanos() as u64,
+      error: r.error.as_ref().map(|e| e.to_string()),
+      stats: r.stats.as_ref().map(|s| DayStatsRecord {
+        generate: StatsRecord::from(&s.generate),
+        part1: StatsRecord::from(&s.part1),
+        part2: StatsRecord::from(&s.part2),
+      }),
+      allocs: r.allocs,
+      cpu_ns: r.cpu_time.map(|t| t.as_nanos() as u64),
+    }
+  }
+}
+
+/// The machine readable form of a whole run. The overall time is the wall
+/// clock time of the run, day_wall_sum is the sum of the wall clock times of
+/// the days and day_cpu_sum is the sum of their CPU times.
+#[derive(Debug, Serialize)]
+pub struct RunRecord {
+  pub days: Vec<DayRecord>,
+  pub overall_ns: u64,
+  pub day_wall_sum_ns: u64,
+  pub day_cpu_sum_ns: Option<u64>,
+}
+
+impl RunRecord {
+  pub fn new(results: &[DayResult], overall: time::Duration) -> Self {
+    RunRecord {
+      days: results.iter().map(DayRecord::from).collect(),
+      overall_ns: overall.as_nanos() as u64,
+      day_wall_sum_ns: day_wall_sum(results).as_nanos() as u64,
+      day_cpu_sum_ns: day_cpu_sum(results).map(|t| t.as_nanos() as u64),
+    }
+  }
+
+  /// Write the run as a JSON document.
+  pub fn write_json(&self, out: &mut dyn io::Write) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, self)?;
+    writeln!(out)
+  }
+
+  /// Write the run as CSV with a row for each day. The overall runtime and
+  /// the sum of the days are repeated on every row. The statistics columns
+  /// are only filled in for repeated runs.
+  pub fn write_csv(&self, out: &mut dyn io::Write) -> io::Result<()> {
+    const STATS: [&str; 4] = ["min", "median", "mean", "std_dev"];
+    let stats_header = ["generate", "part1", "part2"].iter()
+      .flat_map(|phase| STATS.iter().map(move |s| format!(",{phase}_{s}_ns")))
+      .collect::<String>();
+    let empty_stats = ",".repeat(3 * STATS.len());
+    writeln!(out, "day,generate_ns,part1_ns,part1,part2_ns,part2,total_ns,error{stats_header},\
+                   cpu_ns,overall_ns,day_wall_sum_ns,day_cpu_sum_ns")?;
+    for d in &self.days {
+      let field = |s: &Option<String>| s.as_deref().map_or(String::new(), csv_escape);
+      let stats = d.stats.as_ref().map_or(empty_stats.clone(), |s| {
+        s.phases().iter()
+          .map(|p| format!(",{},{},{},{}", p.min_ns, p.median_ns, p.mean_ns, p.std_dev_ns))
+          .collect()
+      });
+      let ns = |t: Option<u64>| t.map_or(String::new(), |t| t.to_string());
+      writeln!(out, "{},{},{},{},{},{},{},{}{},{},{},{},{}", d.day, d.generate_ns, d.part1_ns,
+               field(&d.part1), d.part2_ns, field(&d.part2), d.total_ns, field(&d.error), stats,
+               ns(d.cpu_ns), self.overall_ns, self.day_wall_sum_ns, ns(self.day_cpu_sum_ns))?;
+    }
+    Ok(())
+  }
+}
+
+/// The sum of the wall clock time spent running each day. With several jobs
+/// this includes the time that the days spent waiting for a core.
+pub fn day_wall_sum(results: &[DayResult]) -> time::Duration {
+  results.iter().map(|r| r.total_time()).sum()
+}
+
+/// The sum of the CPU time spent running each day, or None if it couldn't be
+/// measured.
+pub fn day_cpu_sum(results: &[DayResult]) -> Option<time::Duration> {
+  let times: Vec<_> = results.iter().filter_map(|r| r.cpu_time).collect();
+  (!times.is_empty()).then(|| times.iter().sum())
+}
+
+/// Write the results as a markdown table with a row for each day and a final
+/// row with the sums of the days, followed by the overall wall clock time.
+/// With redact, the answers are hidden. Days whose input is missing have no
+/// times.
+pub fn write_markdown(out: &mut dyn io::Write, results: &[DayResult],
+                      overall: time::Duration, redact: bool) -> io::Result<()> {
+  let answer = |r: &DayResult, part: Part| match &r.part(part).1 {
+    Some(_) if redact => "*redacted*".to_string(),
+    Some(a) => format!("`{}`", a.replace('\n', " ")),
+    None if r.is_skipped(part) => "skipped".to_string(),
+    None if matches!(r.error, Some(DayError::MissingInput(_))) => "missing".to_string(),
+    None => "failed".to_string(),
+  };
+  writeln!(out, "| Day | Part 1 | Part 2 | Generator | Part 1 time | Part 2 time | Total |")?;
+  writeln!(out, "|-----|-------:|-------:|----------:|------------:|------------:|------:|")?;
+  for r in results {
+    let times = match r.error {
+      Some(DayError::MissingInput(_)) => "- | - | - | -".to_string(),
+      _ => format!("{:.2?} | {:.2?} | {:.2?} | {:.2?}",
+                   r.generate_time, r.part1.0, r.part2.0, r.total_time()),
+    };
+    writeln!(out, "| {} | {} | {} | {times} |", r.pretty_day(),
+             answer(r, Part::One), answer(r, Part::Two))?;
+  }
+  let sum = |phase: &dyn Fn(&DayResult) -> time::Duration| {
+    results.iter().map(phase).sum::<time::Duration>()
+  };
+  writeln!(out, "| **Sum of day wall times** | | | {:.2?} | {:.2?} | {:.2?} | **{:.2?}** |",
+           sum(&|r| r.generate_time), sum(&|r| r.part1.0), sum(&|r| r.part2.0), day_wall_sum(results))?;
+  writeln!(out)?;
+  writeln!(out, "Overall runtime: {overall:.2?}")?;
+  match day_cpu_sum(results) {
+    Some(cpu) => writeln!(out, "Sum of day CPU times: {cpu:.2?}"),
+    None => Ok(()),
+  }
+}
+
+/// Quote a CSV field if it contains a separator, quote, or newline.
+fn csv_escape(field: &str) -> String {
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+/// The two parts of each day's puzzle.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Part {
+  One,
+  Two,
+}
+
+impl Part {
+  pub const ALL: [Part; 2] = [Part::One, Part::Two];
+}
+
+impl FromStr for Part {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "1" => Ok(Part::One),
+      "2" => Ok(Part::Two),
+      _ => Err(format!("unknown part {s}, expected 1 or 2")),
+    }
+  }
+}
+
+/// A set of days given as a comma separated list of days and ranges, such as
+/// "1,3,5-7".
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DaySelection {
+  days: Vec<usize>,
+}
+
+impl DaySelection {
+  /// The selected days in increasing order.
+  pub fn days(&self) -> &[usize] {
+    &self.days
+  }
+
+  pub fn contains(&self, day: usize) -> bool {
+    self.days.binary_search(&day).is_ok()
+  }
+}
+
+impl FromStr for DaySelection {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let parse = |d: &str| d.trim().parse::<usize>()
+      .map_err(|_| format!("invalid day {d:?} in {s:?}"));
+    let mut days = Vec::new();
+    for item in s.split(',') {
+      match item.split_once('-') {
+        Some((low, high)) => {
+          let (low, high) = (parse(low)?, parse(high)?);
+          if low > high {
+            return Err(format!("invalid range {item:?} in {s:?}"));
+          }
+          days.extend(low..=high);
+        }
+        None => days.push(parse(item)?),
+      }
+    }
+    days.sort_unstable();
+    days.dedup();
+    Ok(DaySelection { days })
+  }
+}
+
+impl fmt::Display for Part {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Part::One => f.pad("1"),
+      Part::Two => f.pad("2"),
+    }
+  }
+}
+
+/// The offsets to the 4 orthogonal neighbors of a cell.
+const NEIGHBORS4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+/// The offsets to the 8 orthogonal and diagonal neighbors of a cell.
+const NEIGHBORS8: [(isize, isize); 8] =
+  [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+/// A rectangular grid of cells that is stored row by row. Positions are
+/// (x, y) with y counting down the rows from the top.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grid<T> {
+  width: usize,
+  height: usize,
+  cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+  /// Create a grid from its cells in row order.
+  pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+    assert_eq!(cells.len(), width * height, "The cells don't fill a {width}x{height} grid");
+    Grid { width, height, cells }
+  }
+
+  /// Create a grid with every cell set to the value.
+  pub fn filled(width: usize, height: usize, value: T) -> Self where T: Clone {
+    Grid::new(width, height, vec![value; width * height])
+  }
+
+  /// Parse a character map with a line for each row. The parse function is
+  /// given the position and character of each cell and returns None for
+  /// characters that aren't allowed.
+  pub fn parse(day: usize, input: &str, mut parse: impl FnMut((usize, usize), char) -> Option<T>)
+               -> Result<Self, ParseError> {
+    let mut cells = Vec::with_capacity(input.len());
+    let mut width = None;
+    let mut height = 0;
+    for (y, line) in input.lines().enumerate() {
+      for (x, (i, c)) in line.char_indices().enumerate() {
+        let cell = parse((x, y), c).ok_or_else(|| {
+          ParseError::new(day, input, &line[i..i + c.len_utf8()], "Invalid character")
+        })?;
+        cells.push(cell);
+      }
+      let row_width = line.chars().count();
+      if *width.get_or_insert(row_width) != row_width {
+        return Err(ParseError::new(day, input, line, "Rows have different widths"));
+      }
+      height += 1;
+    }
+    match width {
+      Some(width) if width > 0 => Ok(Grid::new(width, height, cells)),
+      _ => Err(ParseError::new(day, input, input, "Empty grid")),
+    }
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  /// Is the position inside the grid?
+  pub fn contains(&self, (x, y): (usize, usize)) -> bool {
+    x < self.width && y < self.height
+  }
+
+  /// Get the cell at the position, or None if it is outside the grid.
+  pub fn get(&self, pos: (usize, usize)) -> Option<&T> {
+    self.contains(pos).then(|| &self.cells[pos.1 * self.width + pos.0])
+  }
+
+  pub fn get_mut(&mut self, pos: (usize, usize)) -> Option<&mut T> {
+    match self.contains(pos) {
+      true => Some(&mut self.cells[pos.1 * self.width + pos.0]),
+      false => None,
+    }
+  }
+
+  /// The cells of a row from left to right.
+  pub fn row(&self, y: usize) -> &[T] {
+    &self.cells[y * self.width..(y + 1) * self.width]
+  }
+
+  /// The rows from top to bottom.
+  pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+    self.cells.chunks(self.width)
+  }
+
+  /// The cells of a column from top to bottom.
+  pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+    assert!(x < self.width, "Column {x} is outside the grid");
+    self.cells.iter().skip(x).step_by(self.width)
+  }
+
+  /// Every position in row order. The iterator doesn't borrow the grid, so
+  /// that cells can be updated while iterating.
+  pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+    let width = self.width;
+    (0..self.width * self.height).map(move |i| (i % width, i / width))
+  }
+
+  /// Every cell with its position in row order.
+  pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+    self.positions().zip(&self.cells)
+  }
+
+  pub fn iter_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut T)> {
+    self.positions().zip(&mut self.cells)
+  }
+
+  fn neighbors(&self, (x, y): (usize, usize),
+               offsets: &'static [(isize, isize)]) -> impl Iterator<Item = (usize, usize)> {
+    let (width, height) = (self.width, self.height);
+    offsets.iter().filter_map(move |&(dx, dy)| {
+      let nx = x.checked_add_signed(dx).filter(|&nx| nx < width)?;
+      let ny = y.checked_add_signed(dy).filter(|&ny| ny < height)?;
+      Some((nx, ny))
+    })
+  }
+
+  /// The positions above, left, right and below that are inside the grid.
+  /// Like positions, the iterator doesn't borrow the grid.
+  pub fn neighbors4(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+    self.neighbors(pos, &NEIGHBORS4)
+  }
+
+  /// The positions around, including diagonally, that are inside the grid.
+  pub fn neighbors8(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+    self.neighbors(pos, &NEIGHBORS8)
+  }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+  type Output = T;
+
+  fn index(&self, pos: (usize, usize)) -> &T {
+    self.get(pos).unwrap_or_else(|| panic!("{pos:?} is outside the grid"))
+  }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+  fn index_mut(&mut self, pos: (usize, usize)) -> &mut T {
+    self.get_mut(pos).unwrap_or_else(|| panic!("{pos:?} is outside the grid"))
+  }
+}
+
+/// Print the grid with a line for each row.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for row in self.rows() {
+      for cell in row {
+        write!(f, "{cell}")?;
+      }
+      writeln!(f)?;
+    }
+    Ok(())
+  }
+}
+
+/// A disjoint-set forest over the items 0..n, which tracks the components
+/// that the items have been joined into. It uses path compression and union
+/// by size, so each operation takes nearly constant time.
+#[derive(Clone, Debug)]
+pub struct DisjointSet {
+  parent: Vec<usize>,
+  // the number of items in each root's component
+  size: Vec<usize>,
+  components: usize,
+}
+
+impl DisjointSet {
+  /// Create the set with each item in its own component.
+  pub fn new(len: usize) -> Self {
+    DisjointSet { parent: (0..len).collect(), size: vec![1; len], components: len }
+  }
+
+  /// The number of items.
+  pub fn len(&self) -> usize {
+    self.parent.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.parent.is_empty()
+  }
+
+  /// The number of separate components.
+  pub fn components(&self) -> usize {
+    self.components
+  }
+
+  /// Find the root of the item's component, pointing the items on the way
+  /// directly at the root.
+  pub fn find(&mut self, item: usize) -> usize {
+    let mut root = item;
+    while self.parent[root] != root {
+      root = self.parent[root];
+    }
+    let mut item = item;
+    while self.parent[item] != root {
+      item = std::mem::replace(&mut self.parent[item], root);
+    }
+    root
+  }
+
+  /// Join the components of the two items, returning false if they were
+  /// already in the same one.
+  pub fn union(&mut self, a: usize, b: usize) -> bool {
+    let (a, b) = (self.find(a), self.find(b));
+    if a == b {
+      return false;
+    }
+    let (large, small) = if self.size[a] >= self.size[b] { (a, b) } else { (b, a) };
+    self.parent[small] = large;
+    self.size[large] += self.size[small];
+    self.components -= 1;
+    true
+  }
+
+  /// Are the two items in the same component?
+  pub fn same(&mut self, a: usize, b: usize) -> bool {
+    self.find(a) == self.find(b)
+  }
+
+  /// The number of items in the item's component.
+  pub fn size_of(&mut self, item: usize) -> usize {
+    let root = self.find(item);
+    self.size[root]
+  }
+
+  /// The size of each component in no particular order.
+  pub fn component_sizes(&self) -> Vec<usize> {
+    (0..self.len()).filter(|&i| self.parent[i] == i).map(|i| self.size[i]).collect()
+  }
+}
+
+/// How a day's input is cleaned up before it reaches the generator, so that
+/// files saved by other editors parse the same way. Days where whitespace is
+/// significant turn off the steps that would change it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Normalize {
+  /// remove a leading byte order mark
+  pub bom: bool,
+  /// convert \r\n and lone \r line endings to \n
+  pub line_endings: bool,
+  /// remove the whitespace at the end of each line
+  pub trailing_spaces: bool,
+  /// remove the line endings at the end of the input
+  pub trailing_lines: bool,
+}
+
+impl Normalize {
+  pub const ALL: Normalize = Normalize {
+    bom: true,
+    line_endings: true,
+    trailing_spaces: true,
+    trailing_lines: true,
+  };
+  pub const NONE: Normalize = Normalize {
+    bom: false,
+    line_endings: false,
+    trailing_spaces: false,
+    trailing_lines: false,
+  };
+
+  /// Normalize the input, only copying it when a line has to change.
+  pub fn apply<'a>(&self, input: &'a str) -> Cow<'a, str> {
+    let mut text = Cow::Borrowed(input);
+    if self.bom {
+      if let Some(rest) = input.strip_prefix('\u{feff}') {
+        text = Cow::Borrowed(rest);
+      }
+    }
+    if self.line_endings && text.contains('\r') {
+      text = Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"));
+    }
+    if self.trailing_spaces && text.split('\n').any(|l| l.ends_with(char::is_whitespace)) {
+      text = Cow::Owned(text.split('\n').map(str::trim_end).collect::<Vec<_>>().join("\n"));
+    }
+    if self.trailing_lines {
+      let len = text.trim_end_matches(['\n', '\r']).len();
+      text = match text {
+        Cow::Borrowed(s) => Cow::Borrowed(&s[..len]),
+        Cow::Owned(mut s) => {
+          s.truncate(len);
+          Cow::Owned(s)
+        }
+      };
+    }
+    text
+  }
+}
+
+/// Named settings for a day, such as the smaller sizes that a puzzle's
+/// example uses, as a map from name to value.
+pub type Params = BTreeMap<String, String>;
+
+/// The solution for a single day. Each src/dayN.rs defines a unit struct
+/// DayN that implements this and is registered by day_list!.
+pub trait Solution {
+  /// The day of the puzzle.
+  const DAY: usize;
+  /// The title of the puzzle.
+  const TITLE: &'static str;
+  /// How the input is normalized before the generator runs.
+  const NORMALIZE: Normalize = Normalize::ALL;
+
+  /// The parsed form of the input that is shared by both parts.
+  type Input;
+  type Output1: fmt::Display;
+  type Output2: fmt::Display;
+
+  /// Parse the puzzle input, which the harness has already normalized.
+  fn generator(input: &str) -> Result<Self::Input, ParseError>;
+  fn part1(input: &Self::Input) -> Result<Self::Output1, SolveError>;
+  fn part2(input: &Self::Input) -> Result<Self::Output2, SolveError>;
+
+  /// Set a parameter on the parsed input. Days don't take any parameters
+  /// unless they override this.
+  fn configure(_input: &mut Self::Input, name: &str, _value: &str) -> Result<(), SolveError> {
+    Err(SolveError::new(format!("Unknown parameter {name}")))
+  }
+}
+
+/// The type erased form of a Solution, so that the days can be kept in
+/// a Registry.
+pub trait DaySolution: Sync {
+  /// The day of the puzzle.
+  fn day(&self) -> usize;
+
+  /// The title of the puzzle.
+  fn title(&self) -> &'static str;
+
+  /// The name of the day, which is also the name of its module and input.
+  fn name(&self) -> String {
+    format!("day{}", self.day())
+  }
+
+  /// Normalize the input and run the generator, returning the opaque parsed
+  /// input.
+  fn generate(&self, input: &str) -> Result<Box<dyn Any>, ParseError>;
+
+  /// Solve one part from the result of generate.
+  fn solve(&self, input: &dyn Any, part: Part) -> Result<String, SolveError>;
+
+  /// Solve one part like solve, timing it and counting its allocations when
+  /// that is enabled. The answer is formatted after the timing stops.
+  fn solve_timed(&self, input: &dyn Any, part: Part)
+                 -> (Option<AllocStats>, time::Duration, Result<String, SolveError>);
+
+  /// Apply the parameters to the result of generate.
+  fn configure(&self, input: &mut dyn Any, params: &Params) -> Result<(), SolveError>;
+
+  /// Run the generator and the given parts, timing each of them and counting
+  /// their allocations when that is enabled. The other part is reported as
+  /// skipped. The run stops at the first error, which is recorded in the
+  /// result.
+  fn run_parts(&self, input: &str, parts: &[Part]) -> DayResult {
+    self.run_configured(input, parts, &Params::new())
+  }
+
+  /// Run the given parts like run_parts, after applying the parameters to
+  /// the parsed input. The CPU time of the whole run is measured too.
+  fn run_configured(&self, input: &str, parts: &[Part], params: &Params) -> DayResult {
+    let cpu_start = thread_cpu_time();
+    let mut result = 'run: {
+      let mut result = DayResult { day: self.name(), ..Default::default() };
+      let mut allocs = DayAllocs::default();
+      let (stats, (generate_time, parsed)) = alloc::track(&|| time(&|| self.generate(input)));
+      result.generate_time = generate_time;
+      if let Some(stats) = stats {
+        allocs.record(None, stats);
+        result.allocs = Some(allocs);
+      }
+      let mut parsed = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+          result.error = Some(DayError::Parse(e));
+          break 'run result;
+        }
+      };
+      if let Err(e) = self.configure(parsed.as_mut(), params) {
+        result.error = Some(DayError::Params(e));
+        break 'run result;
+      }
+      for &part in parts {
+        let (stats, elapsed, answer) = self.solve_timed(parsed.as_ref(), part);
+        if let (Some(allocs), Some(stats)) = (&mut result.allocs, stats) {
+          allocs.record(Some(part), stats);
+        }
+        match answer {
+          Ok(answer) => *result.part_mut(part) = (elapsed, Some(answer)),
+          Err(e) => {
+            result.part_mut(part).0 = elapsed;
+            result.error = Some(DayError::Solve(part, e));
+            break;
+          }
+        }
+      }
+      result
+    };
+    result.cpu_time = cpu_start.zip(thread_cpu_time()).map(|(start, end)| end.saturating_sub(start));
+    result
+  }
+
+  /// Run the day warmup times without recording the timings and then repeat
+  /// times, summarizing the timings. A failure stops the runs.
+  fn run_with(&self, input: &str, options: &RunOptions) -> DayResult {
+    for _ in 0..options.warmup {
+      let result = self.run_parts(input, &options.parts);
+      if result.error.is_some() {
+        return result;
+      }
+    }
+    let mut runs = Vec::new();
+    for _ in 0..options.repeat.max(1) {
+      let result = self.run_parts(input, &options.parts);
+      if result.error.is_some() {
+        return result;
+      }
+      runs.push(result);
+    }
+    DayResult::summarize(runs)
+  }
+}
+
+impl<S: Solution + Sync> DaySolution for S where S::Input: 'static {
+  fn day(&self) -> usize {
+    S::DAY
+  }
+
+  fn title(&self) -> &'static str {
+    S::TITLE
+  }
+
+  fn generate(&self, input: &str) -> Result<Box<dyn Any>, ParseError> {
+    Ok(Box::new(S::generator(&S::NORMALIZE.apply(input))?))
+  }
+
+  fn solve(&self, input: &dyn Any, part: Part) -> Result<String, SolveError> {
+    let input = input.downcast_ref::<S::Input>()
+      .expect("Input from a different day");
+    match part {
+      Part::One => S::part1(input).map(|a| a.to_string()),
+      Part::Two => S::part2(input).map(|a| a.to_string()),
+    }
+  }
+
+  fn solve_timed(&self, input: &dyn Any, part: Part)
+                 -> (Option<AllocStats>, time::Duration, Result<String, SolveError>) {
+    let input = input.downcast_ref::<S::Input>()
+      .expect("Input from a different day");
+    match part {
+      Part::One => time_part(&|| S::part1(input)),
+      Part::Two => time_part(&|| S::part2(input)),
+    }
+  }
+
+  fn configure(&self, input: &mut dyn Any, params: &Params) -> Result<(), SolveError> {
+    let input = input.downcast_mut::<S::Input>()
+      .expect("Input from a different day");
+    params.iter().try_for_each(|(name, value)| S::configure(input, name, value))
+  }
+}
+
+/// Time a part and count its allocations, and then format its answer so that
+/// the formatting isn't counted.
+fn time_part<T: fmt::Display>(part: &dyn Fn() -> Result<T, SolveError>)
+                              -> (Option<AllocStats>, time::Duration, Result<String, SolveError>) {
+  let (stats, (elapsed, answer)) = alloc::track(&|| time(part));
+  (stats, elapsed, answer.map(|a| a.to_string()))
+}
+
+/// How the harness runs each day.
+#[derive(Clone, Debug)]
+pub struct RunOptions {
+  /// the number of untimed runs before the timed ones
+  pub warmup: usize,
+  /// the number of timed runs to summarize
+  pub repeat: usize,
+  /// the parts to solve
+  pub parts: Vec<Part>,
+}
+
+impl Default for RunOptions {
+  fn default() -> Self {
+    RunOptions { warmup: 0, repeat: 1, parts: Part::ALL.to_vec() }
+  }
+}
+
+/// Run the day on its own thread, giving up if it takes longer than the
+/// limit. A day that times out can't be stopped, so its thread is left
+/// running in the background until the process exits.
+pub fn run_with_timeout(day: &'static dyn DaySolution, input: &str, options: &RunOptions,
+                        limit: time::Duration) -> DayResult {
+  let (sender, receiver) = mpsc::channel();
+  let (input, options) = (input.to_string(), options.clone());
+  thread::spawn(move || {
+    // The receiver is gone if the day already timed out
+    let _ = sender.send(day.run_with(&input, &options));
+  });
+  receiver.recv_timeout(limit).unwrap_or_else(|_| DayResult::timed_out(day.name(), limit))
+}
+
+/// The list of implemented days in the order that they run.
+pub struct Registry {
+  days: &'static [&'static dyn DaySolution],
+}
+
+impl Registry {
+  pub const fn new(days: &'static [&'static dyn DaySolution]) -> Self {
+    Registry { days }
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &'static dyn DaySolution> {
+    self.days.iter().copied()
+  }
+
+  pub fn len(&self) -> usize {
+    self.days.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.days.is_empty()
+  }
+
+  /// Find the solution for the given day.
+  pub fn by_day(&self, day: usize) -> Option<&'static dyn DaySolution> {
+    self.iter().find(|d| d.day() == day)
+  }
+
+  /// Find the solution by its puzzle title, ignoring case.
+  pub fn by_title(&self, title: &str) -> Option<&'static dyn DaySolution> {
+    self.iter().find(|d| d.title().eq_ignore_ascii_case(title))
+  }
+
+  /// Find the solution for the given day and run only the requested part.
+  pub fn solve(&self, day: usize, part: Part, input: &str) -> Option<Result<String, DayError>> {
+    self.by_day(day).map(|d| {
+      let parsed = d.generate(input).map_err(DayError::Parse)?;
+      d.solve(parsed.as_ref(), part).map_err(|e| DayError::Solve(part, e))
+    })
+  }
+
+  /// The names of the implemented days.
+  pub fn names(&self) -> Vec<String> {
+    self.iter().map(|d| d.name()).collect()
   }
 }
 
 #[macro_export]
 macro_rules! day_list_internal {
-    ( $($day:ident),*) => {
+    ( $(($num:literal, $day:ident, $solution:ident)),*) => {
         // Each day's code should be in src/day?.rs.
         $(pub mod $day;)*
 
-        /// Build a lambda to run each day's code
-        pub const FUNCS : &[&dyn Fn(&str) -> $crate::utils::DayResult] = &[
-            $(&|data| {
-                let (generate_time, input) = $crate::utils::time(&|| $day::generator(data));
-                let part1 = $crate::utils::time(&|| $day::part1(&input));
-                let part2 = $crate::utils::time(&|| $day::part2(&input));
-                $crate::utils::DayResult{day: stringify!($day).to_string(),
-                          generate_time,
-                          part1: (part1.0, part1.1.to_string()),
-                          part2: (part2.0, part2.1.to_string())}},)*
-        ];
+        // Make sure each day registers the solution for its own day.
+        $(const _: () = assert!(
+            <$day::$solution as $crate::utils::Solution>::DAY == $num,
+            concat!(stringify!($solution), "::DAY does not match its module"));)*
 
-        /// Define the list of implemented day names.
-        pub const NAMES: &[&str] = &[$(stringify!($day)),*];
+        /// The registry of the implemented days.
+        pub static DAYS: $crate::utils::Registry = $crate::utils::Registry::new(&[
+            $(&$day::$solution),*
+        ]);
     }
 }
 
 #[macro_export]
 macro_rules! day_list {
   ( $($day:literal),* ) => {
-    paste::paste!{ $crate::utils::day_list_internal!{$( [<day $day>] ),*} }
+    paste::paste!{ $crate::utils::day_list_internal!{$( ($day, [<day $day>], [<Day $day>]) ),*} }
   }
 }
 
 pub use day_list_internal;
-pub use day_list;
\ No newline at end of file
+pub use day_list;
+#[cfg(test)]
+mod tests {
+  use super::{parse_token, run_parallel, run_with_timeout, write_markdown, DayError, DayResult,
+              DaySelection, DisjointSet, Grid, Normalize, ParseError, Part, RunOptions,
+              RunRecord, SolveError, Solution, Stats};
+  use std::time::Duration;
+  use crate::DAYS;
+
+  #[test]
+  fn test_registry() {
+    assert_eq!(DAYS.names().first().map(|n| n.as_str()), Some("day1"));
+    assert_eq!(DAYS.by_day(4).map(|d| d.title()), Some("Printing Department"));
+    assert_eq!(DAYS.by_title("lobby").map(|d| d.day()), Some(3));
+    assert!(DAYS.by_day(0).is_none());
+    assert_eq!(DAYS.solve(1, Part::Two, "R1000"), Some(Ok("10".to_string())));
+  }
+
+  #[test]
+  fn test_day_selection() {
+    let s: DaySelection = "1,3,5-7,3".parse().unwrap();
+    assert_eq!(s.days(), &[1, 3, 5, 6, 7]);
+    assert!(s.contains(6) && !s.contains(4));
+    assert!("7-5".parse::<DaySelection>().is_err());
+    assert!("1,x".parse::<DaySelection>().is_err());
+  }
+
+  #[test]
+  fn test_run_parts() {
+    let r = DAYS.by_day(1).unwrap().run_parts("R1000", &[Part::Two]);
+    assert!(r.is_skipped(Part::One) && !r.is_skipped(Part::Two));
+    assert_eq!(r.part2.1.as_deref(), Some("10"));
+  }
+
+  #[test]
+  fn test_stats() {
+    let samples: Vec<Duration> = [4, 1, 3, 2].iter().map(|&ms| Duration::from_millis(ms)).collect();
+    let stats = Stats::new(&samples);
+    assert_eq!(stats.runs, 4);
+    assert_eq!(stats.min, Duration::from_millis(1));
+    assert_eq!(stats.median, Duration::from_micros(2500));
+    assert_eq!(stats.mean, Duration::from_micros(2500));
+    assert_eq!(stats.std_dev.as_micros(), 1290);
+  }
+
+  #[test]
+  fn test_write_markdown() {
+    let solved = DayResult {
+      day: "day1".to_string(),
+      generate_time: Duration::from_millis(1),
+      part1: (Duration::from_millis(2), Some("3".to_string())),
+      part2: (Duration::from_millis(4), Some("5".to_string())),
+      ..Default::default()
+    };
+    let missing = DayResult::missing_input("day2".to_string(), "input/day2.txt".to_string());
+    let mut out = Vec::new();
+    write_markdown(&mut out, &[solved, missing], Duration::from_millis(9), false).unwrap();
+    let lines: Vec<String> = String::from_utf8(out).unwrap().lines().map(String::from).collect();
+    assert_eq!(lines[2], "| Day 1 | `3` | `5` | 1.00ms | 2.00ms | 4.00ms | 7.00ms |");
+    assert_eq!(lines[3], "| Day 2 | missing | missing | - | - | - | - |");
+    assert_eq!(lines[4], "| **Sum of day wall times** | | | 1.00ms | 2.00ms | 4.00ms | **7.00ms** |");
+    assert_eq!(lines[6], "Overall runtime: 9.00ms");
+  }
+
+  #[test]
+  fn test_write_csv() {
+    let results = [DAYS.by_day(1).unwrap().run_parts("R1000", &Part::ALL)];
+    let mut out = Vec::new();
+    RunRecord::new(&results, Duration::from_nanos(7)).write_csv(&mut out).unwrap();
+    let csv = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    // A header and one row per day, with the run's totals as columns
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with(",cpu_ns,overall_ns,day_wall_sum_ns,day_cpu_sum_ns"));
+    assert_eq!(results[0].cpu_time.is_some(), super::thread_cpu_time().is_some());
+    assert!(lines[1].starts_with("day1,") && lines[1].contains(",7,"));
+  }
+
+  /// A day whose second part never finishes in time.
+  struct Slow;
+
+  impl Solution for Slow {
+    const DAY: usize = 99;
+    const TITLE: &'static str = "Slow";
+    type Input = u64;
+    type Output1 = u64;
+    type Output2 = u64;
+
+    fn part1(input: &u64) -> Result<u64, SolveError> {
+      Ok(*input)
+    }
+
+    fn part2(input: &u64) -> Result<u64, SolveError> {
+      std::thread::sleep(Duration::from_secs(*input));
+      Ok(*input)
+    }
+
+    fn generator(input: &str) -> Result<u64, ParseError> {
+      parse_token(Self::DAY, input, input)
+    }
+  }
+
+  #[test]
+  fn test_run_with_timeout() {
+    let limit = Duration::from_millis(50);
+    let options = RunOptions { parts: vec![Part::One], ..Default::default() };
+    let result = run_with_timeout(&Slow, "5", &options, limit);
+    assert_eq!(result.part1.1.as_deref(), Some("5"));
+    let result = run_with_timeout(&Slow, "5", &RunOptions::default(), limit);
+    assert_eq!(result.error, Some(DayError::TimedOut(limit)));
+    assert_eq!(result.day, "day99");
+  }
+
+  #[test]
+  fn test_grid() {
+    let grid = Grid::parse(1, "ab\ncd\nef", |_, c| Some(c)).unwrap();
+    assert_eq!((grid.width(), grid.height()), (2, 3));
+    assert_eq!(grid[(1, 2)], 'f');
+    assert_eq!(grid.get((2, 0)), None);
+    assert_eq!(grid.row(1), &['c', 'd']);
+    assert_eq!(grid.column(1).collect::<String>(), "bdf");
+    assert_eq!(grid.neighbors4((0, 0)).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+    assert_eq!(grid.neighbors8((1, 1)).count(), 5);
+    assert_eq!(grid.iter().nth(3), Some(((1, 1), &'d')));
+    assert_eq!(grid.to_string(), "ab\ncd\nef\n");
+
+    let e = Grid::parse(1, "ab\nc", |_, c| Some(c)).err().unwrap();
+    assert_eq!((e.line, e.message.as_str()), (2, "Rows have different widths"));
+    let e = Grid::parse(1, "ab\nc!", |_, c| c.is_alphabetic().then_some(c)).err().unwrap();
+    assert_eq!((e.line, e.column, e.text.as_str()), (2, 2, "!"));
+    assert!(Grid::parse(1, "", |_, c| Some(c)).is_err());
+  }
+
+  #[test]
+  fn test_disjoint_set() {
+    let mut set = DisjointSet::new(6);
+    assert_eq!(set.components(), 6);
+    assert!(set.union(0, 1) && set.union(2, 3) && set.union(1, 3));
+    assert!(!set.union(0, 2));
+    assert!(set.same(0, 3) && !set.same(0, 4));
+    assert_eq!((set.size_of(2), set.size_of(5)), (4, 1));
+    assert_eq!(set.components(), 3);
+    let mut sizes = set.component_sizes();
+    sizes.sort_unstable();
+    assert_eq!(sizes, vec![1, 1, 4]);
+  }
+
+  #[test]
+  fn test_normalize() {
+    let input = "\u{feff}1 2 \r\n3\r4\t\n\n\n";
+    assert_eq!(Normalize::ALL.apply(input), "1 2\n3\n4");
+    assert_eq!(Normalize::NONE.apply(input), input);
+    let keep_spaces = Normalize { trailing_spaces: false, ..Normalize::ALL };
+    assert_eq!(keep_spaces.apply(input), "1 2 \n3\n4\t");
+    assert!(matches!(Normalize::ALL.apply("1\n2\n"), std::borrow::Cow::Borrowed("1\n2")));
+
+    // Windows line endings don't change the answers
+    assert_eq!(DAYS.solve(5, Part::One, "3-5\r\n10-14\r\n\r\n1\r\n5\r\n"),
+               Some(Ok("1".to_string())));
+    assert_eq!(DAYS.solve(6, Part::Two, "1 2\r\n3 4\r\n* +\r\n"), Some(Ok("37".to_string())));
+  }
+
+  #[test]
+  fn test_run_parallel() {
+    let items: Vec<u64> = (0..50).collect();
+    let expected: Vec<u64> = items.iter().map(|i| i * i).collect();
+    for jobs in [0, 1, 3, 100] {
+      assert_eq!(run_parallel(jobs, &items, &|i| i * i), expected);
+    }
+  }
+
+  #[test]
+  fn test_parse_error() {
+    let input = "L10\nR5\nX7\n";
+    let e = ParseError::new(1, input, &input[8..9], "bad rotation");
+    assert_eq!((e.line, e.column, e.text.as_str()), (3, 2, "7"));
+    let e = parse_token::<u32>(1, input, &input[4..6]).unwrap_err();
+    assert_eq!((e.line, e.column, e.text.as_str()), (2, 1, "R5"));
+    assert!(matches!(DAYS.solve(1, Part::One, input), Some(Err(DayError::Parse(_)))));
+  }
+}
@@ -0,0 +1,110 @@
+use crate::utils::{DayResult, Part};
+use colored::Colorize;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Polls a set of files for changes to their modification time or size.
+/// Files that don't exist are watched for being created.
+pub struct Watcher {
+  paths: Vec<PathBuf>,
+  stamps: Vec<Option<(SystemTime, u64)>>,
+}
+
+impl Watcher {
+  /// Watch the given paths. The first call to changed reports a change so
+  /// that the first run happens straight away.
+  pub fn new(paths: Vec<PathBuf>) -> Self {
+    let stamps = vec![Some((SystemTime::UNIX_EPOCH, u64::MAX)); paths.len()];
+    Watcher { paths, stamps }
+  }
+
+  pub fn paths(&self) -> &[PathBuf] {
+    &self.paths
+  }
+
+  fn stamp(path: &PathBuf) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+  }
+
+  /// Have any of the files changed since the last call?
+  pub fn changed(&mut self) -> bool {
+    let stamps: Vec<_> = self.paths.iter().map(Self::stamp).collect();
+    let changed = stamps != self.stamps;
+    self.stamps = stamps;
+    changed
+  }
+}
+
+/// A part's answer next to the answer from the previous run.
+#[derive(Debug, Eq, PartialEq)]
+pub struct AnswerChange {
+  pub part: Part,
+  pub previous: Option<String>,
+  pub current: Option<String>,
+}
+
+impl AnswerChange {
+  /// Compare the answers of the given parts with those from the previous
+  /// run, if there was one.
+  pub fn compare(previous: Option<&DayResult>, current: &DayResult,
+                 parts: &[Part]) -> Vec<AnswerChange> {
+    parts.iter().map(|&part| AnswerChange {
+      part,
+      previous: previous.and_then(|p| p.part(part).1.clone()),
+      current: current.part(part).1.clone(),
+    }).collect()
+  }
+}
+
+impl fmt::Display for AnswerChange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let current = self.current.as_deref().unwrap_or("-");
+    write!(f, "Part {} {}", self.part, current.bold())?;
+    match &self.previous {
+      Some(previous) if self.current.as_ref() == Some(previous) =>
+        write!(f, " {}", "(unchanged)".dimmed()),
+      Some(previous) => write!(f, " {}", format!("(was {previous})").yellow().bold()),
+      None => write!(f, " {}", "(new)".dimmed()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{AnswerChange, Watcher};
+  use crate::utils::{DayResult, Part};
+  use std::fs;
+
+  #[test]
+  fn test_watcher() {
+    let path = std::env::temp_dir().join(format!("aoc2025-watch-{}.txt", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let mut watcher = Watcher::new(vec![path.clone()]);
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+    fs::write(&path, "1").unwrap();
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+    fs::write(&path, "12").unwrap();
+    assert!(watcher.changed());
+    fs::remove_file(&path).unwrap();
+    assert!(watcher.changed());
+  }
+
+  #[test]
+  fn test_compare() {
+    let result = |answer: &str| DayResult {
+      part1: (Default::default(), Some(answer.to_string())),
+      ..Default::default()
+    };
+    let (previous, current) = (result("1"), result("2"));
+    let changes = AnswerChange::compare(Some(&previous), &current, &Part::ALL);
+    assert_eq!(changes[0].previous.as_deref(), Some("1"));
+    assert_eq!(changes[0].current.as_deref(), Some("2"));
+    assert_eq!(changes[1], AnswerChange { part: Part::Two, previous: None, current: None });
+    assert!(AnswerChange::compare(None, &current, &[Part::One])[0].previous.is_none());
+  }
+}